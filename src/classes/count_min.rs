@@ -0,0 +1,74 @@
+use cpython::*;
+use crate::classes::Key;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::to_python_result;
+use crate::utils::for_each_chunk;
+use std::cell::RefCell;
+
+// Approximate frequencies of items of a stream kept in a fixed size table, estimates never
+// underestimate true frequencies. Items may be ints, floats, strings or bytes, sketches created
+// with the same width, depth and seed can be merged
+py_class!(pub class CountMinSketch |py| {
+    data sketch: RefCell<stat_funcs::CountMinSketch>;
+
+    def __new__(_cls, width: usize, depth: usize, seed: u64 = 0) -> PyResult<CountMinSketch> {
+        let sketch = to_python_result(py, stat_funcs::CountMinSketch::new(width, depth, seed))?;
+        CountMinSketch::create_instance(py, RefCell::new(sketch))
+    }
+
+    @classmethod def from_bytes(cls, data: &PyBytes) -> PyResult<CountMinSketch> {
+        let sketch = to_python_result(py, stat_funcs::CountMinSketch::from_bytes(data.data(py)))?;
+        CountMinSketch::create_instance(py, RefCell::new(sketch))
+    }
+
+    def to_bytes(&self) -> PyResult<PyBytes> {
+        Ok(PyBytes::new(py, &self.sketch(py).borrow().to_bytes()))
+    }
+
+    def __reduce__(&self) -> PyResult<(PyObject, (PyBytes,))> {
+        let from_bytes = py.get_type::<CountMinSketch>().as_object().getattr(py, "from_bytes")?;
+        Ok((from_bytes, (self.to_bytes(py)?,)))
+    }
+
+    def add(&self, x: Key, count: u64 = 1) -> PyResult<PyObject> {
+        self.sketch(py).borrow_mut().add(&x, count);
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        for_each_chunk::<Key, _>(py, &xs, |chunk| {
+            let mut sketch = self.sketch(py).borrow_mut();
+            for x in chunk {
+                sketch.add(x, 1);
+            }
+            Ok(())
+        })?;
+        Ok(py.None())
+    }
+
+    def estimate(&self, x: Key) -> PyResult<u64> {
+        Ok(self.sketch(py).borrow().estimate(&x))
+    }
+
+    def merge(&self, other: &CountMinSketch) -> PyResult<PyObject> {
+        let other = other.sketch(py).borrow().clone();
+        to_python_result(py, self.sketch(py).borrow_mut().merge(&other))?;
+        Ok(py.None())
+    }
+
+    def total(&self) -> PyResult<u64> {
+        Ok(self.sketch(py).borrow().total())
+    }
+
+    def width(&self) -> PyResult<usize> {
+        Ok(self.sketch(py).borrow().width())
+    }
+
+    def depth(&self) -> PyResult<usize> {
+        Ok(self.sketch(py).borrow().depth())
+    }
+
+    def seed(&self) -> PyResult<u64> {
+        Ok(self.sketch(py).borrow().seed())
+    }
+});
@@ -0,0 +1,111 @@
+use cpython::*;
+use super::Item;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::{to_python_result, MyError};
+use crate::utils::for_each_chunk;
+use std::cell::RefCell;
+
+// Greenwald-Khanna quantile summary of ints, floats or strings with deterministic error
+// guarantee, rank of value returned by query(phi) differs from phi * count() by no more than
+// epsilon * count(). Ints and floats are compared exactly, strings can't be mixed with numbers.
+// Summaries built by separate processes can be merged and pickled
+py_class!(pub class GKSummary |py| {
+    data summary: RefCell<stat_funcs::GKSummary<Item>>;
+
+    def __new__(_cls, epsilon: f64 = 0.01) -> PyResult<GKSummary> {
+        let summary = to_python_result(py, stat_funcs::GKSummary::new(epsilon))?;
+        GKSummary::create_instance(py, RefCell::new(summary))
+    }
+
+    @classmethod def from_bytes(cls, data: &PyBytes) -> PyResult<GKSummary> {
+        let summary = to_python_result(py, stat_funcs::GKSummary::from_bytes(data.data(py)))?;
+        GKSummary::create_instance(py, RefCell::new(summary))
+    }
+
+    def to_bytes(&self) -> PyResult<PyBytes> {
+        Ok(PyBytes::new(py, &self.summary(py).borrow().to_bytes()))
+    }
+
+    def __reduce__(&self) -> PyResult<(PyObject, (PyBytes,))> {
+        let from_bytes = py.get_type::<GKSummary>().as_object().getattr(py, "from_bytes")?;
+        Ok((from_bytes, (self.to_bytes(py)?,)))
+    }
+
+    def insert(&self, x: Item) -> PyResult<PyObject> {
+        to_python_result(py, insert_values(&mut self.summary(py).borrow_mut(), &[x]))?;
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        for_each_chunk::<Item, _>(py, &xs, |chunk| {
+            insert_values(&mut self.summary(py).borrow_mut(), chunk)
+        })?;
+        Ok(py.None())
+    }
+
+    def merge(&self, other: &GKSummary) -> PyResult<PyObject> {
+        let other = other.summary(py).borrow().clone();
+        let mut summary = self.summary(py).borrow_mut();
+        if let Ok(x) = other.min() {
+            to_python_result(py, check_item(&summary, &x))?;
+        }
+        to_python_result(py, summary.merge(&other))?;
+        Ok(py.None())
+    }
+
+    def query(&self, phi: f64) -> PyResult<Item> {
+        to_python_result(py, self.summary(py).borrow().query(phi))
+    }
+
+    // estimated fraction of values which are less than or equal to x
+    def rank(&self, x: Item) -> PyResult<f64> {
+        let summary = self.summary(py).borrow();
+        to_python_result(py, check_item(&summary, &x).and_then(|_| summary.rank(&x)))
+    }
+
+    def count(&self) -> PyResult<u64> {
+        Ok(self.summary(py).borrow().count())
+    }
+
+    def epsilon(&self) -> PyResult<f64> {
+        Ok(self.summary(py).borrow().epsilon())
+    }
+
+    // number of stored tuples
+    def size(&self) -> PyResult<usize> {
+        Ok(self.summary(py).borrow().size())
+    }
+
+    def min(&self) -> PyResult<Item> {
+        to_python_result(py, self.summary(py).borrow().min())
+    }
+
+    def max(&self) -> PyResult<Item> {
+        to_python_result(py, self.summary(py).borrow().max())
+    }
+});
+
+/// NaNs have no rank and strings can't be compared with numbers, so such values are rejected,
+/// the values of the summary are of the same kind as its minimum
+fn check_item(summary: &stat_funcs::GKSummary<Item>, x: &Item) -> Result<(), MyError> {
+    x.check()?;
+
+    match summary.min() {
+        Ok(ref min) if min.partial_cmp(x).is_none() => Err(MyError::MixedTypes),
+        _ => Ok(()),
+    }
+}
+
+/// All the values are checked before any of them is inserted
+fn insert_values(summary: &mut stat_funcs::GKSummary<Item>, xs: &[Item]) -> Result<(), MyError> {
+    for x in xs {
+        check_item(summary, x)?;
+
+        if xs[0].partial_cmp(x).is_none() {
+            return Err(MyError::MixedTypes);
+        }
+    }
+
+    summary.extend(xs);
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use cpython::*;
+use crate::classes::Key;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::to_python_result;
+use crate::utils::for_each_chunk;
+use std::cell::RefCell;
+
+// Approximate top-k most frequent items of a stream, memory usage is bounded by k no matter how
+// many distinct items the stream contains. Items may be ints, floats, strings or bytes
+py_class!(pub class HeavyHitters |py| {
+    data sketch: RefCell<stat_funcs::HeavyHitters<Key>>;
+
+    def __new__(_cls, k: usize) -> PyResult<HeavyHitters> {
+        let sketch = to_python_result(py, stat_funcs::HeavyHitters::new(k))?;
+        HeavyHitters::create_instance(py, RefCell::new(sketch))
+    }
+
+    def push(&self, x: Key) -> PyResult<PyObject> {
+        self.sketch(py).borrow_mut().push(x);
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        for_each_chunk::<Key, _>(py, &xs, |chunk| {
+            self.sketch(py).borrow_mut().extend(chunk);
+            Ok(())
+        })?;
+        Ok(py.None())
+    }
+
+    def merge(&self, other: &HeavyHitters) -> PyResult<PyObject> {
+        let other = other.sketch(py).borrow().clone();
+        to_python_result(py, self.sketch(py).borrow_mut().merge(&other))?;
+        Ok(py.None())
+    }
+
+    // (item, count, error) triples of at most n most frequent items, true frequency of an item
+    // is between count - error and count
+    def top(&self, n: Option<usize> = None) -> PyResult<Vec<(Key, u64, u64)>> {
+        let mut top = self.sketch(py).borrow().top();
+        if let Some(n) = n {
+            top.truncate(n);
+        }
+        Ok(top)
+    }
+
+    def count(&self) -> PyResult<u64> {
+        Ok(self.sketch(py).borrow().count())
+    }
+
+    def k(&self) -> PyResult<usize> {
+        Ok(self.sketch(py).borrow().k())
+    }
+});
@@ -0,0 +1,62 @@
+use cpython::*;
+use crate::classes::Key;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::to_python_result;
+use crate::utils::for_each_chunk;
+use std::cell::RefCell;
+
+// Approximate number of distinct items of a stream, memory usage is 2^precision bytes no matter
+// how many items were added. Items may be ints, floats, strings or bytes, sketches of the same
+// precision can be merged
+py_class!(pub class HyperLogLog |py| {
+    data sketch: RefCell<stat_funcs::HyperLogLog>;
+
+    def __new__(_cls, precision: i64 = 14) -> PyResult<HyperLogLog> {
+        let sketch = to_python_result(py, stat_funcs::HyperLogLog::new(precision))?;
+        HyperLogLog::create_instance(py, RefCell::new(sketch))
+    }
+
+    @classmethod def from_bytes(cls, data: &PyBytes) -> PyResult<HyperLogLog> {
+        let sketch = to_python_result(py, stat_funcs::HyperLogLog::from_bytes(data.data(py)))?;
+        HyperLogLog::create_instance(py, RefCell::new(sketch))
+    }
+
+    def to_bytes(&self) -> PyResult<PyBytes> {
+        Ok(PyBytes::new(py, &self.sketch(py).borrow().to_bytes()))
+    }
+
+    def __reduce__(&self) -> PyResult<(PyObject, (PyBytes,))> {
+        let from_bytes = py.get_type::<HyperLogLog>().as_object().getattr(py, "from_bytes")?;
+        Ok((from_bytes, (self.to_bytes(py)?,)))
+    }
+
+    def add(&self, x: Key) -> PyResult<PyObject> {
+        self.sketch(py).borrow_mut().add(&x);
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        for_each_chunk::<Key, _>(py, &xs, |chunk| {
+            let mut sketch = self.sketch(py).borrow_mut();
+            for x in chunk {
+                sketch.add(x);
+            }
+            Ok(())
+        })?;
+        Ok(py.None())
+    }
+
+    def merge(&self, other: &HyperLogLog) -> PyResult<PyObject> {
+        let other = other.sketch(py).borrow().clone();
+        to_python_result(py, self.sketch(py).borrow_mut().merge(&other))?;
+        Ok(py.None())
+    }
+
+    def cardinality(&self) -> PyResult<f64> {
+        Ok(self.sketch(py).borrow().cardinality())
+    }
+
+    def precision(&self) -> PyResult<u8> {
+        Ok(self.sketch(py).borrow().precision())
+    }
+});
@@ -0,0 +1,129 @@
+use cpython::*;
+use super::key::I64_BOUND;
+use crate::stat_funcs::errors::MyError;
+use crate::stat_funcs::{ByteReader, ByteWriter, Serializable};
+use std::cmp::Ordering;
+
+/// Orderable python value accepted by KllSketch and GKSummary.
+///
+/// Ints and floats are compared exactly, the same way python compares them, strings can't be
+/// compared with numbers
+#[derive(Clone, Debug)]
+crate enum Item {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl Item {
+    crate fn check(&self) -> Result<(), MyError> {
+        match self {
+            Item::Float(x) if x.is_nan() => Err(MyError::NanInData),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Exact comparison of an int with a float, the float is never rounded to an int or vice versa
+fn cmp_int_float(x: i64, y: f64) -> Option<Ordering> {
+    if y.is_nan() {
+        None
+    } else if y >= I64_BOUND {
+        Some(Ordering::Less)
+    } else if y < -I64_BOUND {
+        Some(Ordering::Greater)
+    } else {
+        // integral part of y fits into i64, so it is converted without rounding
+        let int_part = y.trunc();
+
+        match x.cmp(&(int_part as i64)) {
+            Ordering::Equal => int_part.partial_cmp(&y),
+            other => Some(other),
+        }
+    }
+}
+
+impl PartialEq for Item {
+    fn eq(&self, other: &Item) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Item {
+    fn partial_cmp(&self, other: &Item) -> Option<Ordering> {
+        match (self, other) {
+            (Item::Int(x), Item::Int(y)) => x.partial_cmp(y),
+            (Item::Float(x), Item::Float(y)) => x.partial_cmp(y),
+            (Item::Int(x), Item::Float(y)) => cmp_int_float(*x, *y),
+            (Item::Float(x), Item::Int(y)) => cmp_int_float(*y, *x).map(Ordering::reverse),
+            (Item::Str(x), Item::Str(y)) => x.partial_cmp(y),
+            _ => None,
+        }
+    }
+}
+
+/// Tag word of the variant followed by the value, strings are stored as their length in bytes
+/// followed by utf-8 bytes
+impl Serializable for Item {
+    fn write_to(&self, writer: &mut ByteWriter) {
+        match self {
+            Item::Int(x) => {
+                writer.put_u64(0);
+                writer.put_u64(*x as u64);
+            }
+            Item::Float(x) => {
+                writer.put_u64(1);
+                writer.put_f64(*x);
+            }
+            Item::Str(x) => {
+                writer.put_u64(2);
+                writer.put_u64(x.len() as u64);
+                writer.put_bytes(x.as_bytes());
+            }
+        }
+    }
+
+    fn read_from(reader: &mut ByteReader<'_>) -> Result<Self, MyError> {
+        match reader.get_u64()? {
+            0 => reader.get_u64().map(|x| Item::Int(x as i64)),
+            1 => reader.get_f64().map(Item::Float),
+            2 => {
+                let len = reader.get_u64()? as usize;
+                let bytes = reader.get_bytes(len)?.to_vec();
+                String::from_utf8(bytes)
+                    .map(Item::Str)
+                    .map_err(|_| MyError::InvalidSketchData)
+            }
+            _ => Err(MyError::InvalidSketchData),
+        }
+    }
+}
+
+impl<'source> FromPyObject<'source> for Item {
+    fn extract(py: Python<'_>, obj: &'source PyObject) -> PyResult<Self> {
+        if obj.cast_as::<PyLong>(py).is_ok() {
+            i64::extract(py, obj).map(Item::Int)
+        } else if obj.cast_as::<PyFloat>(py).is_ok() {
+            f64::extract(py, obj).map(Item::Float)
+        } else if obj.cast_as::<PyString>(py).is_ok() {
+            String::extract(py, obj).map(Item::Str)
+        } else {
+            Err(PyErr::new::<exc::TypeError, _>(
+                py,
+                "unsupported item type, expected int, float or str",
+            ))
+        }
+    }
+}
+
+impl ToPyObject for Item {
+    type ObjectType = PyObject;
+
+    fn to_py_object(&self, py: Python<'_>) -> PyObject {
+        match self {
+            Item::Int(x) => x.to_py_object(py).into_object(),
+            Item::Float(x) => x.to_py_object(py).into_object(),
+            Item::Str(x) => x.to_py_object(py).into_object(),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use cpython::*;
+use crate::stat_funcs::{SeededHasher, StableHash};
+use ordered_float::OrderedFloat;
+
+// i64 range is [-2^63, 2^63), the bound is exactly representable as f64
+crate const I64_BOUND: f64 = 9_223_372_036_854_775_808.0;
+
+/// Hashable python value stored by sketches, integers, floats, strings and bytes are supported.
+///
+/// Floats having integral values are stored as integers, so that equal python values such as 1
+/// and 1.0 are treated as the same item, just like python dicts do
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+crate enum Key {
+    Int(i64),
+    Float(OrderedFloat<f64>),
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl<'source> FromPyObject<'source> for Key {
+    fn extract(py: Python<'_>, obj: &'source PyObject) -> PyResult<Self> {
+        if obj.cast_as::<PyLong>(py).is_ok() {
+            i64::extract(py, obj).map(Key::Int)
+        } else if obj.cast_as::<PyFloat>(py).is_ok() {
+            f64::extract(py, obj).map(Key::from)
+        } else if obj.cast_as::<PyString>(py).is_ok() {
+            String::extract(py, obj).map(Key::Str)
+        } else if let Ok(bytes) = obj.cast_as::<PyBytes>(py) {
+            Ok(Key::Bytes(bytes.data(py).to_vec()))
+        } else {
+            Err(PyErr::new::<exc::TypeError, _>(
+                py,
+                "unsupported item type, expected int, float, str or bytes",
+            ))
+        }
+    }
+}
+
+impl From<f64> for Key {
+    fn from(x: f64) -> Self {
+        if x.fract() == 0.0 && x >= -I64_BOUND && x < I64_BOUND {
+            Key::Int(x as i64)
+        } else {
+            Key::Float(OrderedFloat(x))
+        }
+    }
+}
+
+/// Tag byte of the variant followed by little-endian bytes of the value, all NaNs have the same
+/// encoding because they are equal as keys
+impl StableHash for Key {
+    fn stable_hash(&self, hasher: &mut SeededHasher) {
+        match self {
+            Key::Int(x) => {
+                hasher.write(&[0]);
+                hasher.write_u64(*x as u64);
+            }
+            Key::Float(x) => {
+                let x = if x.is_nan() { std::f64::NAN } else { x.into_inner() };
+                hasher.write(&[1]);
+                hasher.write_u64(x.to_bits());
+            }
+            Key::Str(x) => {
+                hasher.write(&[2]);
+                hasher.write(x.as_bytes());
+            }
+            Key::Bytes(x) => {
+                hasher.write(&[3]);
+                hasher.write(x);
+            }
+        }
+    }
+}
+
+impl ToPyObject for Key {
+    type ObjectType = PyObject;
+
+    fn to_py_object(&self, py: Python<'_>) -> PyObject {
+        match self {
+            Key::Int(x) => x.to_py_object(py).into_object(),
+            Key::Float(x) => x.into_inner().to_py_object(py).into_object(),
+            Key::Str(x) => x.to_py_object(py).into_object(),
+            Key::Bytes(x) => PyBytes::new(py, x).into_object(),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use cpython::*;
+use super::Item;
+use crate::stat_funcs::errors::{to_python_result, MyError};
+use crate::stat_funcs::{self, kll_rank_error};
+use crate::utils::for_each_chunk;
+use ordered_float::OrderedFloat;
+use std::cell::RefCell;
+use std::mem;
+
+/// Sketch of python values, its item type is chosen by the first pushed value. Int sketch is
+/// converted into float one as soon as it meets a float, which is lossy for ints beyond 2^53,
+/// strings can't be mixed with numbers
+#[derive(Clone, Debug)]
+crate enum Sketch {
+    Empty { k: usize, seed: Option<u64> },
+    Int(stat_funcs::KllSketch<i64>),
+    Float(stat_funcs::KllSketch<OrderedFloat<f64>>),
+    Str(stat_funcs::KllSketch<String>),
+}
+
+impl Sketch {
+    fn new(k: usize, seed: Option<u64>) -> Result<Self, MyError> {
+        // check parameters right away, though the actual sketch is created by the first push
+        stat_funcs::KllSketch::<i64>::new(k, seed)?;
+        Ok(Sketch::Empty { k, seed })
+    }
+
+    fn k(&self) -> usize {
+        match self {
+            Sketch::Empty { k, .. } => *k,
+            Sketch::Int(s) => s.k(),
+            Sketch::Float(s) => s.k(),
+            Sketch::Str(s) => s.k(),
+        }
+    }
+
+    fn count(&self) -> u64 {
+        match self {
+            Sketch::Empty { .. } => 0,
+            Sketch::Int(s) => s.count(),
+            Sketch::Float(s) => s.count(),
+            Sketch::Str(s) => s.count(),
+        }
+    }
+
+    /// Convert int sketch into float one, other sketches are left as is
+    fn promote(&mut self) {
+        let sketch = mem::replace(self, Sketch::Empty { k: 0, seed: None });
+
+        *self = match sketch {
+            Sketch::Int(s) => Sketch::Float(s.map(|x| OrderedFloat(x as f64))),
+            other => other,
+        };
+    }
+
+    fn push(&mut self, x: Item) -> Result<(), MyError> {
+        x.check()?;
+
+        if let Sketch::Empty { k, seed } = *self {
+            *self = match x {
+                Item::Int(_) => Sketch::Int(stat_funcs::KllSketch::new(k, seed)?),
+                Item::Float(_) => Sketch::Float(stat_funcs::KllSketch::new(k, seed)?),
+                Item::Str(_) => Sketch::Str(stat_funcs::KllSketch::new(k, seed)?),
+            };
+        }
+
+        if let Item::Float(_) = x {
+            self.promote();
+        }
+
+        match (self, x) {
+            (Sketch::Int(s), Item::Int(x)) => s.push(x),
+            (Sketch::Float(s), Item::Int(x)) => s.push(OrderedFloat(x as f64)),
+            (Sketch::Float(s), Item::Float(x)) => s.push(OrderedFloat(x)),
+            (Sketch::Str(s), Item::Str(x)) => s.push(x),
+            _ => return Err(MyError::MixedTypes),
+        }
+
+        Ok(())
+    }
+
+    fn merge(&mut self, mut other: Sketch) -> Result<(), MyError> {
+        if self.k() != other.k() {
+            return Err(MyError::IncompatibleSketches);
+        }
+
+        if let Sketch::Empty { .. } = other {
+            return Ok(());
+        } else if let Sketch::Empty { .. } = *self {
+            *self = other;
+            return Ok(());
+        }
+
+        if let Sketch::Float(_) = other {
+            self.promote();
+        } else if let Sketch::Float(_) = *self {
+            other.promote();
+        }
+
+        match (self, other) {
+            (Sketch::Int(a), Sketch::Int(b)) => a.merge(&b),
+            (Sketch::Float(a), Sketch::Float(b)) => a.merge(&b),
+            (Sketch::Str(a), Sketch::Str(b)) => a.merge(&b),
+            _ => Err(MyError::MixedTypes),
+        }
+    }
+
+    fn quantile(&self, q: f64) -> Result<Item, MyError> {
+        match self {
+            Sketch::Empty { .. } => Err(MyError::NoEnoughDataForQuantiles),
+            Sketch::Int(s) => s.quantile(q).map(Item::Int),
+            Sketch::Float(s) => s.quantile(q).map(|x| Item::Float(x.into_inner())),
+            Sketch::Str(s) => s.quantile(q).map(Item::Str),
+        }
+    }
+
+    fn rank(&self, x: &Item) -> Result<f64, MyError> {
+        x.check()?;
+
+        match (self, x) {
+            (Sketch::Empty { .. }, _) => Err(MyError::NoEnoughDataForQuantiles),
+            (Sketch::Int(s), Item::Int(x)) => s.rank(x),
+            (Sketch::Int(s), Item::Float(x)) => s.rank_by(|y| (*y as f64) <= *x),
+            (Sketch::Float(s), Item::Int(x)) => s.rank(&OrderedFloat(*x as f64)),
+            (Sketch::Float(s), Item::Float(x)) => s.rank(&OrderedFloat(*x)),
+            (Sketch::Str(s), Item::Str(x)) => s.rank(x),
+            _ => Err(MyError::MixedTypes),
+        }
+    }
+
+    fn min_max(&self) -> Result<(Item, Item), MyError> {
+        match self {
+            Sketch::Empty { .. } => Err(MyError::NoEnoughDataForMinMax),
+            Sketch::Int(s) => Ok((Item::Int(s.min()?), Item::Int(s.max()?))),
+            Sketch::Float(s) => Ok((
+                Item::Float(s.min()?.into_inner()),
+                Item::Float(s.max()?.into_inner()),
+            )),
+            Sketch::Str(s) => Ok((Item::Str(s.min()?), Item::Str(s.max()?))),
+        }
+    }
+}
+
+// KLL quantiles sketch of ints, floats or strings with memory usage of about 3k items, rank error
+// of estimated quantiles doesn't exceed rank_error() with probability 99%. Ints are converted to
+// floats once the sketch meets a float, which rounds ints beyond 2^53. Compaction is randomized,
+// sketches created with the same seed and fed with the same data give the same results.
+// Sketches with the same k can be merged, but they can't be serialized
+py_class!(pub class KllSketch |py| {
+    data sketch: RefCell<Sketch>;
+
+    def __new__(_cls, k: usize = 200, seed: Option<u64> = None) -> PyResult<KllSketch> {
+        let sketch = to_python_result(py, Sketch::new(k, seed))?;
+        KllSketch::create_instance(py, RefCell::new(sketch))
+    }
+
+    def push(&self, x: Item) -> PyResult<PyObject> {
+        to_python_result(py, self.sketch(py).borrow_mut().push(x))?;
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        for_each_chunk::<Item, _>(py, &xs, |chunk| {
+            let mut sketch = self.sketch(py).borrow_mut();
+            for x in chunk {
+                sketch.push(x.clone())?;
+            }
+            Ok(())
+        })?;
+        Ok(py.None())
+    }
+
+    def merge(&self, other: &KllSketch) -> PyResult<PyObject> {
+        let other = other.sketch(py).borrow().clone();
+        to_python_result(py, self.sketch(py).borrow_mut().merge(other))?;
+        Ok(py.None())
+    }
+
+    def quantile(&self, q: f64) -> PyResult<Item> {
+        to_python_result(py, self.sketch(py).borrow().quantile(q))
+    }
+
+    // estimated fraction of items which are less than or equal to x
+    def rank(&self, x: Item) -> PyResult<f64> {
+        to_python_result(py, self.sketch(py).borrow().rank(&x))
+    }
+
+    def count(&self) -> PyResult<u64> {
+        Ok(self.sketch(py).borrow().count())
+    }
+
+    def min(&self) -> PyResult<Item> {
+        to_python_result(py, self.sketch(py).borrow().min_max().map(|x| x.0))
+    }
+
+    def max(&self) -> PyResult<Item> {
+        to_python_result(py, self.sketch(py).borrow().min_max().map(|x| x.1))
+    }
+
+    def rank_error(&self) -> PyResult<f64> {
+        Ok(kll_rank_error(self.sketch(py).borrow().k()))
+    }
+
+    def k(&self) -> PyResult<usize> {
+        Ok(self.sketch(py).borrow().k())
+    }
+});
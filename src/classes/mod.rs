@@ -0,0 +1,25 @@
+// Python classes wrapping stateful rust accumulators
+
+mod count_min;
+mod gk;
+mod heavy_hitters;
+mod hyperloglog;
+mod item;
+mod key;
+mod kll;
+mod p2;
+mod reservoir;
+mod running_stats;
+mod tdigest;
+
+crate use self::count_min::CountMinSketch;
+crate use self::gk::GKSummary;
+crate use self::heavy_hitters::HeavyHitters;
+crate use self::hyperloglog::HyperLogLog;
+crate use self::item::Item;
+crate use self::key::Key;
+crate use self::kll::KllSketch;
+crate use self::p2::{P2Quantile, P2Quantiles};
+crate use self::reservoir::Reservoir;
+crate use self::running_stats::RunningStats;
+crate use self::tdigest::TDigest;
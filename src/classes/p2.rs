@@ -0,0 +1,93 @@
+use cpython::*;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::to_python_result;
+use crate::utils::{extract_samples, for_each_chunk, is_in_memory};
+use std::cell::RefCell;
+
+// Running estimate of a single quantile p by P-square algorithm, only five values are stored no
+// matter how many of them have been pushed
+py_class!(pub class P2Quantile |py| {
+    data p2: RefCell<stat_funcs::P2Quantiles>;
+
+    def __new__(_cls, p: f64) -> PyResult<P2Quantile> {
+        let p2 = to_python_result(py, stat_funcs::P2Quantiles::new(&[p]))?;
+        P2Quantile::create_instance(py, RefCell::new(p2))
+    }
+
+    def push(&self, x: f64) -> PyResult<PyObject> {
+        to_python_result(py, self.p2(py).borrow_mut().push(x))?;
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        extend_p2(py, self.p2(py), xs)
+    }
+
+    def quantile(&self) -> PyResult<f64> {
+        to_python_result(py, self.p2(py).borrow().quantiles().map(|qs| qs[0]))
+    }
+
+    def count(&self) -> PyResult<u64> {
+        Ok(self.p2(py).borrow().count())
+    }
+
+    def min(&self) -> PyResult<f64> {
+        to_python_result(py, self.p2(py).borrow().min())
+    }
+
+    def max(&self) -> PyResult<f64> {
+        to_python_result(py, self.p2(py).borrow().max())
+    }
+});
+
+// Running estimates of several quantiles at once by extended P-square algorithm, 2 * len(ps) + 3
+// values are stored
+py_class!(pub class P2Quantiles |py| {
+    data p2: RefCell<stat_funcs::P2Quantiles>;
+
+    def __new__(_cls, ps: Vec<f64>) -> PyResult<P2Quantiles> {
+        let p2 = to_python_result(py, stat_funcs::P2Quantiles::new(&ps))?;
+        P2Quantiles::create_instance(py, RefCell::new(p2))
+    }
+
+    def push(&self, x: f64) -> PyResult<PyObject> {
+        to_python_result(py, self.p2(py).borrow_mut().push(x))?;
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        extend_p2(py, self.p2(py), xs)
+    }
+
+    // estimates in the order of ps
+    def quantiles(&self) -> PyResult<Vec<f64>> {
+        to_python_result(py, self.p2(py).borrow().quantiles())
+    }
+
+    def count(&self) -> PyResult<u64> {
+        Ok(self.p2(py).borrow().count())
+    }
+
+    def min(&self) -> PyResult<f64> {
+        to_python_result(py, self.p2(py).borrow().min())
+    }
+
+    def max(&self) -> PyResult<f64> {
+        to_python_result(py, self.p2(py).borrow().max())
+    }
+});
+
+/// Push values of python iterable into the estimator, same for both classes
+fn extend_p2(
+    py: Python<'_>,
+    p2: &RefCell<stat_funcs::P2Quantiles>,
+    xs: PyObject,
+) -> PyResult<PyObject> {
+    if is_in_memory(py, &xs) {
+        let ys = extract_samples::<f64>(py, &xs)?;
+        to_python_result(py, p2.borrow_mut().extend(ys.as_slice()))?;
+    } else {
+        for_each_chunk::<f64, _>(py, &xs, |chunk| p2.borrow_mut().extend(chunk))?;
+    }
+    Ok(py.None())
+}
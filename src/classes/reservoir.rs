@@ -0,0 +1,41 @@
+use cpython::*;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::to_python_result;
+use std::cell::RefCell;
+
+// Uniform random sample of k items of a stream of unknown length, any python objects may be
+// pushed. Reservoirs created with the same seed and fed with the same data hold the same sample
+py_class!(pub class Reservoir |py| {
+    data reservoir: RefCell<stat_funcs::Reservoir<PyObject>>;
+
+    def __new__(_cls, k: usize, seed: Option<u64> = None) -> PyResult<Reservoir> {
+        let reservoir = to_python_result(py, stat_funcs::Reservoir::new(k, seed))?;
+        Reservoir::create_instance(py, RefCell::new(reservoir))
+    }
+
+    def push(&self, x: PyObject) -> PyResult<PyObject> {
+        self.reservoir(py).borrow_mut().push(x);
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        for x in xs.iter(py)? {
+            self.reservoir(py).borrow_mut().push(x?);
+        }
+        Ok(py.None())
+    }
+
+    // current sample, it contains min(k, count()) items
+    def sample(&self) -> PyResult<Vec<PyObject>> {
+        let reservoir = self.reservoir(py).borrow();
+        Ok(reservoir.sample().iter().map(|x| x.clone_ref(py)).collect())
+    }
+
+    def count(&self) -> PyResult<u64> {
+        Ok(self.reservoir(py).borrow().count())
+    }
+
+    def k(&self) -> PyResult<usize> {
+        Ok(self.reservoir(py).borrow().k())
+    }
+});
@@ -0,0 +1,84 @@
+use cpython::*;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::to_python_result;
+use crate::utils::extract_samples;
+use std::cell::RefCell;
+
+// Streaming mean/variance accumulator, values may be pushed one by one or in batches, none of
+// them are stored. Accumulators can be merged and pickled, so partial results computed by
+// separate processes may be reduced into global statistics
+py_class!(pub class RunningStats |py| {
+    data stats: RefCell<stat_funcs::RunningStats<f64>>;
+
+    def __new__(_cls) -> PyResult<RunningStats> {
+        RunningStats::create_instance(py, RefCell::new(stat_funcs::RunningStats::new()))
+    }
+
+    def push(&self, x: f64) -> PyResult<PyObject> {
+        self.stats(py).borrow_mut().push(x);
+        Ok(py.None())
+    }
+
+    def extend(&self, xs: PyObject) -> PyResult<PyObject> {
+        let ys = extract_samples::<f64>(py, &xs)?;
+        self.stats(py).borrow_mut().extend(ys.as_slice());
+        Ok(py.None())
+    }
+
+    def merge(&self, other: &RunningStats) -> PyResult<PyObject> {
+        // other's state is copied first, so that merging an accumulator with itself works
+        let other = other.stats(py).borrow().clone();
+        self.stats(py).borrow_mut().merge(&other);
+        Ok(py.None())
+    }
+
+    // NotImplemented lets python try the reflected operation of the other operand
+    def __add__(lhs, rhs) -> PyResult<PyObject> {
+        let (a, b) = match (lhs.cast_as::<RunningStats>(py), rhs.cast_as::<RunningStats>(py)) {
+            (Ok(a), Ok(b)) => (a.stats(py).borrow().clone(), b.stats(py).borrow().clone()),
+            _ => return Ok(py.NotImplemented()),
+        };
+        RunningStats::create_instance(py, RefCell::new(a + b)).map(|x| x.into_object())
+    }
+
+    def __getstate__(&self) -> PyResult<(usize, f64, f64, f64, f64)> {
+        Ok(self.stats(py).borrow().state())
+    }
+
+    def __setstate__(&self, state: (usize, f64, f64, f64, f64)) -> PyResult<PyObject> {
+        *self.stats(py).borrow_mut() = stat_funcs::RunningStats::from_state(state);
+        Ok(py.None())
+    }
+
+    def count(&self) -> PyResult<usize> {
+        Ok(self.stats(py).borrow().count())
+    }
+
+    def mean(&self) -> PyResult<f64> {
+        to_python_result(py, self.stats(py).borrow().mean())
+    }
+
+    def variance(&self) -> PyResult<f64> {
+        to_python_result(py, self.stats(py).borrow().variance())
+    }
+
+    def pvariance(&self) -> PyResult<f64> {
+        to_python_result(py, self.stats(py).borrow().pvariance())
+    }
+
+    def stdev(&self) -> PyResult<f64> {
+        to_python_result(py, self.stats(py).borrow().stdev())
+    }
+
+    def pstdev(&self) -> PyResult<f64> {
+        to_python_result(py, self.stats(py).borrow().pstdev())
+    }
+
+    def min(&self) -> PyResult<f64> {
+        to_python_result(py, self.stats(py).borrow().min())
+    }
+
+    def max(&self) -> PyResult<f64> {
+        to_python_result(py, self.stats(py).borrow().max())
+    }
+});
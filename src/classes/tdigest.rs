@@ -0,0 +1,76 @@
+use cpython::*;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::to_python_result;
+use crate::utils::{extract_samples, for_each_chunk, is_in_memory};
+use std::cell::RefCell;
+
+// Streaming quantiles estimator, data is summarized by about compression / 2 centroids, so that
+// percentiles of unbounded streams may be tracked. Digests built by separate processes can be
+// merged and pickled
+py_class!(pub class TDigest |py| {
+    data digest: RefCell<stat_funcs::TDigest>;
+
+    def __new__(_cls, compression: f64 = 100.0) -> PyResult<TDigest> {
+        let digest = to_python_result(py, stat_funcs::TDigest::new(compression))?;
+        TDigest::create_instance(py, RefCell::new(digest))
+    }
+
+    @classmethod def from_bytes(cls, data: &PyBytes) -> PyResult<TDigest> {
+        let digest = to_python_result(py, stat_funcs::TDigest::from_bytes(data.data(py)))?;
+        TDigest::create_instance(py, RefCell::new(digest))
+    }
+
+    def to_bytes(&self) -> PyResult<PyBytes> {
+        Ok(PyBytes::new(py, &self.digest(py).borrow().to_bytes()))
+    }
+
+    def __reduce__(&self) -> PyResult<(PyObject, (PyBytes,))> {
+        let from_bytes = py.get_type::<TDigest>().as_object().getattr(py, "from_bytes")?;
+        Ok((from_bytes, (self.to_bytes(py)?,)))
+    }
+
+    def push(&self, x: f64) -> PyResult<PyObject> {
+        to_python_result(py, self.digest(py).borrow_mut().push(x))?;
+        Ok(py.None())
+    }
+
+    def update(&self, xs: PyObject) -> PyResult<PyObject> {
+        if is_in_memory(py, &xs) {
+            let ys = extract_samples::<f64>(py, &xs)?;
+            to_python_result(py, self.digest(py).borrow_mut().extend(ys.as_slice()))?;
+        } else {
+            for_each_chunk::<f64, _>(py, &xs, |chunk| self.digest(py).borrow_mut().extend(chunk))?;
+        }
+        Ok(py.None())
+    }
+
+    def merge(&self, other: &TDigest) -> PyResult<PyObject> {
+        let other = other.digest(py).borrow().clone();
+        self.digest(py).borrow_mut().merge(&other);
+        Ok(py.None())
+    }
+
+    def quantile(&self, q: f64) -> PyResult<f64> {
+        to_python_result(py, self.digest(py).borrow_mut().quantile(q))
+    }
+
+    def cdf(&self, x: f64) -> PyResult<f64> {
+        to_python_result(py, self.digest(py).borrow_mut().cdf(x))
+    }
+
+    def count(&self) -> PyResult<u64> {
+        Ok(self.digest(py).borrow().count())
+    }
+
+    def min(&self) -> PyResult<f64> {
+        to_python_result(py, self.digest(py).borrow().min())
+    }
+
+    def max(&self) -> PyResult<f64> {
+        to_python_result(py, self.digest(py).borrow().max())
+    }
+
+    def compression(&self) -> PyResult<f64> {
+        Ok(self.digest(py).borrow().compression())
+    }
+});
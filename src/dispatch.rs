@@ -0,0 +1,315 @@
+// Polymorphic functions which inspect input data and choose the right monomorphized rust
+// implementation
+
+use cpython::buffer::PyBuffer;
+use cpython::*;
+use crate::classes::Key;
+use crate::stat_funcs;
+use crate::stat_funcs::errors::{to_python_result, without_gil, MyError};
+use crate::stat_funcs::{NanOutput, NanPolicy};
+use crate::utils::{as_ordered_floats, extract_samples, into_ordered_floats, iter_to_vec};
+use std::collections::HashMap;
+
+/// Type of a single python value
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Int,
+    Float,
+    Str,
+    Bytes,
+    Object,
+}
+
+/// Input data converted into the most specific rust type, Object is used for mixed data and
+/// for values which have no rust counterpart
+enum Data {
+    Int(Vec<i64>),
+    UInt(Vec<u64>),
+    Float(Vec<f64>),
+    Str(Vec<String>),
+    Bytes(Vec<Vec<u8>>),
+    Object(Vec<PyObject>),
+}
+
+/// Bools are ints in python, but they are kept as objects, so that results are bools as well
+fn kind_of(py: Python<'_>, x: &PyObject) -> Kind {
+    if x.cast_as::<PyBool>(py).is_ok() {
+        Kind::Object
+    } else if x.cast_as::<PyLong>(py).is_ok() {
+        Kind::Int
+    } else if x.cast_as::<PyFloat>(py).is_ok() {
+        Kind::Float
+    } else if x.cast_as::<PyString>(py).is_ok() {
+        Kind::Str
+    } else if x.cast_as::<PyBytes>(py).is_ok() {
+        Kind::Bytes
+    } else {
+        Kind::Object
+    }
+}
+
+/// Common kind of all the values, mix of integers and floats is processed as python objects,
+/// because big integers can't be converted into floats without losing precision
+fn common_kind(py: Python<'_>, xs: &[PyObject]) -> Kind {
+    let mut kinds = xs.iter().map(|x| kind_of(py, x));
+
+    match kinds.next() {
+        None => Kind::Object,
+        Some(first) => kinds.fold(first, |acc, kind| if acc == kind { acc } else { Kind::Object }),
+    }
+}
+
+#[inline]
+fn extract_all<T>(py: Python<'_>, xs: &[PyObject]) -> Option<Vec<T>>
+where
+    for<'a> T: FromPyObject<'a>,
+{
+    xs.iter().map(|x| x.extract(py).ok()).collect()
+}
+
+/// Buffers are converted according to their format without looking at the values
+fn convert_buffer(py: Python<'_>, xs: &PyObject) -> PyResult<Option<Data>> {
+    let format = match PyBuffer::get(py, xs) {
+        Ok(buf) => buf.format().to_bytes().last().cloned(),
+        Err(_) => return Ok(None),
+    };
+
+    Ok(match format {
+        Some(b'f') | Some(b'd') | Some(b'e') => {
+            Some(Data::Float(extract_samples(py, xs)?.into_vec()))
+        }
+        Some(b'b') | Some(b'h') | Some(b'i') | Some(b'l') | Some(b'q') | Some(b'n') => {
+            Some(Data::Int(extract_samples(py, xs)?.into_vec()))
+        }
+        Some(b'B') | Some(b'H') | Some(b'I') | Some(b'L') | Some(b'Q') | Some(b'N') => {
+            Some(Data::UInt(extract_samples(py, xs)?.into_vec()))
+        }
+        _ => None,
+    })
+}
+
+fn convert(py: Python<'_>, xs: &PyObject) -> PyResult<Data> {
+    if let Some(data) = convert_buffer(py, xs)? {
+        return Ok(data);
+    }
+
+    let objs: Vec<PyObject> = iter_to_vec(py, xs)?;
+
+    // values which can't be converted (integers which don't fit into 64 bits, strings with
+    // lone surrogates, etc.) are processed as python objects
+    let data = match common_kind(py, &objs) {
+        Kind::Int => extract_all(py, &objs)
+            .map(Data::Int)
+            .or_else(|| extract_all(py, &objs).map(Data::UInt)),
+        Kind::Float => extract_all(py, &objs).map(Data::Float),
+        Kind::Str => extract_all(py, &objs).map(Data::Str),
+        Kind::Bytes => objs
+            .iter()
+            .map(|x| x.cast_as::<PyBytes>(py).ok().map(|b| b.data(py).to_vec()))
+            .collect::<Option<Vec<Vec<u8>>>>()
+            .map(Data::Bytes),
+        Kind::Object => None,
+    };
+
+    Ok(data.unwrap_or(Data::Object(objs)))
+}
+
+#[inline]
+fn to_object<T: ToPyObject>(py: Python<'_>, x: T) -> PyObject {
+    x.to_py_object(py).into_object()
+}
+
+/// Frequencies of arbitrary hashable python objects, objects are grouped by their hash first and
+/// then compared for equality within each group
+fn object_counts(py: Python<'_>, xs: Vec<PyObject>) -> PyResult<Vec<(PyObject, u64)>> {
+    let mut groups: HashMap<isize, Vec<(PyObject, u64)>> = HashMap::new();
+
+    for x in xs {
+        let group = groups.entry(x.hash(py)?).or_insert_with(Vec::new);
+        let mut found = false;
+
+        for (y, n) in group.iter_mut() {
+            if x.rich_compare(py, &*y, CompareOp::Eq)?.is_true(py)? {
+                *n += 1;
+                found = true;
+                break;
+            }
+        }
+
+        if !found {
+            group.push((x, 1));
+        }
+    }
+
+    Ok(groups.into_iter().flat_map(|(_, g)| g).collect())
+}
+
+/// Float members of mixed data are treated according to nan_policy, returns the data to be
+/// used and a flag telling whether the result must be NaN, see NanPolicy::apply
+fn apply_nan_policy(
+    py: Python<'_>,
+    mut xs: Vec<PyObject>,
+    nan_policy: NanPolicy,
+) -> PyResult<(Vec<PyObject>, bool)> {
+    let is_nan = |x: &PyObject| x.cast_as::<PyFloat>(py).map_or(false, |x| x.value(py).is_nan());
+
+    if !xs.iter().any(|x| is_nan(x)) {
+        return Ok((xs, false));
+    }
+
+    match nan_policy {
+        NanPolicy::Raise => to_python_result(py, Err(MyError::NanInData)),
+        NanPolicy::Omit => {
+            xs.retain(|x| !is_nan(x));
+            Ok((xs, false))
+        }
+        NanPolicy::Propagate => Ok((xs, true)),
+    }
+}
+
+/// Kth order statistic of arbitrary python objects supporting comparison, the first failed
+/// comparison stops the selection
+fn object_kth(
+    py: Python<'_>,
+    mut xs: Vec<PyObject>,
+    k: usize,
+    seed: Option<u64>,
+) -> PyResult<PyObject> {
+    if k >= xs.len() {
+        return to_python_result(py, Err(MyError::KthOutOfRange { k, len: xs.len() }));
+    }
+
+    stat_funcs::try_select_by(&mut xs, k, seed, |a, b| a.compare(py, b))?;
+
+    Ok(xs.swap_remove(k))
+}
+
+/// Float data is treated according to nan_policy, the same as by mode_float
+crate fn mode_py(py: Python<'_>, xs: PyObject, nan_policy: NanPolicy) -> PyResult<PyObject> {
+    match convert(py, &xs)? {
+        Data::Int(ys) => without_gil(py, || stat_funcs::int_mode(&ys)).map(|x| to_object(py, x)),
+        Data::UInt(ys) => without_gil(py, || stat_funcs::int_mode(&ys)).map(|x| to_object(py, x)),
+        Data::Float(mut ys) => without_gil(py, || {
+            let propagate = nan_policy.apply_mut(&mut ys)?;
+            f64::nan_or_else(propagate, || {
+                stat_funcs::mode(as_ordered_floats(&ys)).map(|x| x.into_inner())
+            })
+        })
+        .map(|x| to_object(py, x)),
+        Data::Str(ys) => without_gil(py, || stat_funcs::mode(&ys)).map(|x| to_object(py, x)),
+        Data::Bytes(ys) => without_gil(py, || stat_funcs::mode(&ys))
+            .map(|x| PyBytes::new(py, &x).into_object()),
+        Data::Object(ys) => {
+            let counts = object_counts(py, ys)?;
+            to_python_result(py, stat_funcs::unique_mode(counts))
+        }
+    }
+}
+
+/// Float data and floats mixed with other values are treated according to nan_policy, the same
+/// as by kth_elem_float
+crate fn kth_elem_py(
+    py: Python<'_>,
+    xs: PyObject,
+    k: usize,
+    seed: Option<u64>,
+    nan_policy: NanPolicy,
+) -> PyResult<PyObject> {
+    match convert(py, &xs)? {
+        Data::Int(mut ys) => {
+            without_gil(py, || stat_funcs::radix_kth_stat(&mut ys, k, seed))
+                .map(|x| to_object(py, x))
+        }
+        Data::UInt(mut ys) => {
+            without_gil(py, || stat_funcs::radix_kth_stat(&mut ys, k, seed))
+                .map(|x| to_object(py, x))
+        }
+        Data::Float(mut ys) => without_gil(py, || {
+            let propagate = nan_policy.apply_mut(&mut ys)?;
+            f64::nan_or_else(propagate, || {
+                let mut ys = into_ordered_floats(ys);
+                stat_funcs::radix_kth_stat(&mut ys, k, seed).map(|x| x.into_inner())
+            })
+        })
+        .map(|x| to_object(py, x)),
+        Data::Str(ys) => {
+            // strings are selected by references, which are cheap to swap
+            let mut refs = ys.iter().map(|x| x.as_str()).collect::<Vec<&str>>();
+            without_gil(py, || stat_funcs::kth_stat(&mut refs, k, seed)).map(|x| to_object(py, x))
+        }
+        Data::Bytes(ys) => {
+            let mut refs = ys.iter().map(|x| &x[..]).collect::<Vec<&[u8]>>();
+            without_gil(py, || stat_funcs::kth_stat(&mut refs, k, seed))
+                .map(|x| PyBytes::new(py, x).into_object())
+        }
+        Data::Object(ys) => match apply_nan_policy(py, ys, nan_policy)? {
+            (_, true) => Ok(to_object(py, std::f64::NAN)),
+            (ys, false) => object_kth(py, ys, k, seed),
+        },
+    }
+}
+
+crate fn count_distinct_py(py: Python<'_>, xs: PyObject) -> PyResult<usize> {
+    match convert(py, &xs)? {
+        Data::Int(ys) => without_gil(py, || stat_funcs::int_count_distinct(&ys)),
+        Data::UInt(ys) => without_gil(py, || stat_funcs::int_count_distinct(&ys)),
+        Data::Float(ys) => {
+            // all NaNs are counted as a single value
+            let ys = as_ordered_floats(&ys);
+            without_gil(py, || stat_funcs::count_distinct(ys))
+        }
+        Data::Str(ys) => without_gil(py, || stat_funcs::count_distinct(&ys)),
+        Data::Bytes(ys) => without_gil(py, || stat_funcs::count_distinct(&ys)),
+        Data::Object(ys) => match extract_all::<Key>(py, &ys) {
+            // keys of equal python numbers such as 1, 1.0 and True are equal, so mixed data is
+            // counted exactly, only values which aren't keys need python comparison
+            Some(keys) => without_gil(py, || stat_funcs::count_distinct(&keys)),
+            None => object_counts(py, ys).map(|counts| counts.len()),
+        },
+    }
+}
+
+/// Pick elements of a python iterable by indices chosen by the given function of its length,
+/// sequences are indexed directly, so only the chosen elements are fetched
+fn pick_by_indices<F>(py: Python<'_>, xs: &PyObject, choose: F) -> PyResult<Vec<PyObject>>
+where
+    F: FnOnce(usize) -> Result<Vec<usize>, MyError>,
+{
+    if let Ok(seq) = xs.cast_as::<PySequence>(py) {
+        let indices = to_python_result(py, choose(seq.len(py)? as usize))?;
+        indices
+            .into_iter()
+            .map(|idx| seq.get_item(py, idx as isize))
+            .collect()
+    } else {
+        let ys = iter_to_vec::<PyObject>(py, xs)?;
+        let indices = to_python_result(py, choose(ys.len()))?;
+        Ok(indices.into_iter().map(|idx| ys[idx].clone_ref(py)).collect())
+    }
+}
+
+crate fn sample_py(
+    py: Python<'_>,
+    xs: PyObject,
+    k: usize,
+    seed: Option<u64>,
+) -> PyResult<Vec<PyObject>> {
+    pick_by_indices(py, &xs, |n| stat_funcs::sample_indices(n, k, seed))
+}
+
+crate fn weighted_sample_py(
+    py: Python<'_>,
+    xs: PyObject,
+    weights: PyObject,
+    k: usize,
+    seed: Option<u64>,
+) -> PyResult<Vec<PyObject>> {
+    let weights = extract_samples::<f64>(py, &weights)?;
+
+    pick_by_indices(py, &xs, |n| {
+        if n != weights.as_slice().len() {
+            return Err(MyError::WeightsLengthMismatch);
+        }
+        stat_funcs::weighted_sample_indices(weights.as_slice(), k, seed)
+    })
+}
@@ -18,12 +18,17 @@ macro_rules! gen_wrapper {
     //    provided if necessary;
     // 4) kth_elem_f64_py and kth_elem_f32_py then must be used by rust-cpython library
     //    as wrappers between python and rust code;
+    // 5) Python list is converted while holding the GIL, rust function itself is called with
+    //    the GIL released, so other python threads are not blocked by the computation;
+    // 6) Objects supporting buffer protocol are read in-place without conversion, data is
+    //    copied only if rust function needs a mutable slice (mut variants of the macro);
     //
     // Example of code generated by gen_wrapper!(kth_stat, (kth_elem_f64_py, [k::usize] => f64)):
     //
     // crate fn kth_stat_py(py: Python<'_>, xs: PyObject, k: usize) -> PyResult<f64> {
-    //      let ys = pylist_to_vec(py, xs)?;
-    //      to_python_result(py, kth_stat(ys, k))
+    //      let samples = extract_samples(py, &xs)?;
+    //      let ys = samples.as_slice();
+    //      without_gil(py, || kth_stat(ys, k))
     // }
 
     // rust func name to be called from python, kth_stat for example
@@ -35,10 +40,11 @@ macro_rules! gen_wrapper {
 
         $(
             // generate wrapper function
-            crate fn $func_name(py: Python<'_>, xs: PyObject, $($arg: $arg_type)*) ->
+            crate fn $func_name(py: Python<'_>, xs: PyObject $(, $arg: $arg_type)*) ->
                                                                         PyResult<$ret_type> {
-                let ys = pylist_to_vec::<$ret_type>(py, &xs)?;
-                to_python_result(py, stat_funcs::$rust_func_name(&ys, $($arg)*))
+                let samples = extract_samples(py, &xs)?;
+                let ys = samples.as_slice();
+                without_gil(py, || stat_funcs::$rust_func_name(ys $(, $arg)*))
             }
         )+
 
@@ -48,10 +54,134 @@ macro_rules! gen_wrapper {
     (mut $rust_func_name:ident, $( ($func_name:ident, [$($arg:ident::$arg_type:ty),*] =>
                                                                         $ret_type:ty) ),+) => {
         $(
-            crate fn $func_name(py: Python<'_>, xs: PyObject, $($arg: $arg_type)*) ->
+            crate fn $func_name(py: Python<'_>, xs: PyObject $(, $arg: $arg_type)*) ->
                                                                             PyResult<$ret_type> {
-                let mut ys = pylist_to_vec::<$ret_type>(py, &xs)?;
-                to_python_result(py, stat_funcs::$rust_func_name(&mut ys, $($arg)*))
+                let mut ys = extract_samples(py, &xs)?.into_vec();
+                without_gil(py, || stat_funcs::$rust_func_name(&mut ys $(, $arg)*))
+            }
+        )+
+    };
+
+    // Macro rules for single pass statistics
+    //
+    // Lists, tuples and buffers are processed by $rust_func_name as usual, all the other python
+    // iterables are consumed chunk by chunk by accumulator $acc (which must implement
+    // stat_funcs::Accumulator) without materializing the whole sequence, $finish method of
+    // the accumulator returns the result
+
+    (stream $rust_func_name:ident, $acc:ident::$finish:ident,
+                                        $( ($func_name:ident, [] => $ret_type:ty) ),+) => {
+        $(
+            crate fn $func_name(py: Python<'_>, xs: PyObject) -> PyResult<$ret_type> {
+                if is_in_memory(py, &xs) {
+                    let samples = extract_samples(py, &xs)?;
+                    let ys = samples.as_slice();
+                    without_gil(py, || stat_funcs::$rust_func_name(ys))
+                } else {
+                    let mut acc = stat_funcs::$acc::default();
+                    for_each_chunk(py, &xs, |chunk| acc.update(chunk))?;
+                    to_python_result(py, acc.$finish())
+                }
+            }
+        )+
+    };
+
+    // Macro rules for float statistics
+    //
+    // Generated functions accept one more trailing argument nan_policy: NanPolicy which defines
+    // how NaN values of the input are treated, input elements are always f64
+
+    (nan mut $rust_func_name:ident, $( ($func_name:ident, [$($arg:ident::$arg_type:ty),*] =>
+                                                                        $ret_type:ty) ),+) => {
+        $(
+            crate fn $func_name(py: Python<'_>, xs: PyObject $(, $arg: $arg_type)*,
+                                nan_policy: NanPolicy) -> PyResult<$ret_type> {
+                let mut ys = extract_samples::<f64>(py, &xs)?.into_vec();
+                without_gil(py, || {
+                    let propagate = nan_policy.apply_mut(&mut ys)?;
+                    NanOutput::nan_or_else(propagate, || {
+                        stat_funcs::$rust_func_name(&mut ys $(, $arg)*)
+                    })
+                })
+            }
+        )+
+    };
+
+    (nan ord mut $rust_func_name:ident, $( ($func_name:ident, [$($arg:ident::$arg_type:ty),*] =>
+                                                                        $ret_type:ty) ),+) => {
+        $(
+            crate fn $func_name(py: Python<'_>, xs: PyObject $(, $arg: $arg_type)*,
+                                nan_policy: NanPolicy) -> PyResult<$ret_type> {
+                let mut ys = extract_samples::<f64>(py, &xs)?.into_vec();
+                without_gil(py, || {
+                    let propagate = nan_policy.apply_mut(&mut ys)?;
+                    let mut ys = into_ordered_floats(ys);
+                    NanOutput::nan_or_else(propagate, || {
+                        let res =
+                            stat_funcs::$rust_func_name::<OrderedFloat<f64>>(&mut ys $(, $arg)*);
+                        res.map(|x| x.into_floats())
+                    })
+                })
+            }
+        )+
+    };
+
+    (nan stream $rust_func_name:ident, $acc:ident::$finish:ident,
+                                        $( ($func_name:ident, [] => $ret_type:ty) ),+) => {
+        $(
+            crate fn $func_name(py: Python<'_>, xs: PyObject, nan_policy: NanPolicy) ->
+                                                                        PyResult<$ret_type> {
+                if is_in_memory(py, &xs) {
+                    let samples = extract_samples::<f64>(py, &xs)?;
+                    let ys = samples.as_slice();
+                    without_gil(py, || {
+                        let (ys, propagate) = nan_policy.apply(ys)?;
+                        NanOutput::nan_or_else(propagate, || stat_funcs::$rust_func_name(&ys))
+                    })
+                } else {
+                    let mut acc = stat_funcs::$acc::<f64>::default();
+                    let mut propagate = false;
+                    for_each_chunk::<f64, _>(py, &xs, |chunk| {
+                        let (chunk, has_nan) = nan_policy.apply(chunk)?;
+                        propagate |= has_nan;
+                        acc.update(&chunk)
+                    })?;
+                    to_python_result(py, NanOutput::nan_or_else(propagate, || acc.$finish()))
+                }
+            }
+        )+
+    };
+
+    (nan stream ord $rust_func_name:ident, $acc:ident::$finish:ident,
+                                        $( ($func_name:ident, [] => $ret_type:ty) ),+) => {
+        $(
+            crate fn $func_name(py: Python<'_>, xs: PyObject, nan_policy: NanPolicy) ->
+                                                                        PyResult<$ret_type> {
+                if is_in_memory(py, &xs) {
+                    let samples = extract_samples::<f64>(py, &xs)?;
+                    let ys = samples.as_slice();
+                    without_gil(py, || {
+                        let (ys, propagate) = nan_policy.apply(ys)?;
+                        NanOutput::nan_or_else(propagate, || {
+                            let res = stat_funcs::$rust_func_name::<OrderedFloat<f64>>(
+                                as_ordered_floats(&ys),
+                            );
+                            res.map(|x| x.into_floats())
+                        })
+                    })
+                } else {
+                    let mut acc = stat_funcs::$acc::<OrderedFloat<f64>>::default();
+                    let mut propagate = false;
+                    for_each_chunk::<f64, _>(py, &xs, |chunk| {
+                        let (chunk, has_nan) = nan_policy.apply(chunk)?;
+                        propagate |= has_nan;
+                        acc.update(as_ordered_floats(&chunk))
+                    })?;
+                    let res = NanOutput::nan_or_else(propagate, || {
+                        acc.$finish().map(|x| x.into_floats())
+                    });
+                    to_python_result(py, res)
+                }
             }
         )+
     };
@@ -61,11 +191,14 @@ macro_rules! gen_wrapper {
     (ord $rust_func_name:ident, $( ($func_name:ident, [$($arg:ident::$arg_type:ty),*] =>
                                                                             $ret_type:ty) ),+) => {
         $(
-            crate fn $func_name(py: Python<'_>, xs: PyObject, $($arg: $arg_type)*) ->
+            crate fn $func_name(py: Python<'_>, xs: PyObject $(, $arg: $arg_type)*) ->
                                                                     PyResult<$ret_type> {
-                let ys = extract_ordered_floats(py, &xs)?;
-                let res = stat_funcs::$rust_func_name::<OrderedFloat<$ret_type>>(&ys, $($arg)*);
-                to_python_result(py, res.map(|x| x.into()))
+                let samples = extract_samples::<$ret_type>(py, &xs)?;
+                let ys = as_ordered_floats(samples.as_slice());
+                without_gil(py, || {
+                    let res = stat_funcs::$rust_func_name::<OrderedFloat<$ret_type>>(ys $(, $arg)*);
+                    res.map(|x| x.into())
+                })
             }
         )+
     };
@@ -73,10 +206,14 @@ macro_rules! gen_wrapper {
     (ord mut $rust_func_name:ident, $( ($func_name:ident, [$($arg:ident::$arg_type:ty),*] =>
                                                                             $ret_type:ty) ),+) => {
         $(
-            crate fn $func_name(py: Python<'_>, xs: PyObject) -> PyResult<$ret_type> {
-                let mut ys = extract_ordered_floats(py, &xs)?;
-                let res = stat_funcs::$rust_func_name::<OrderedFloat<$ret_type>>(&mut ys, $($arg)*);
-                to_python_result(py, res.map(|x| x.into()))
+            crate fn $func_name(py: Python<'_>, xs: PyObject $(, $arg: $arg_type)*) ->
+                                                                    PyResult<$ret_type> {
+                let mut ys = into_ordered_floats(extract_samples::<$ret_type>(py, &xs)?.into_vec());
+                without_gil(py, || {
+                    let res =
+                        stat_funcs::$rust_func_name::<OrderedFloat<$ret_type>>(&mut ys $(, $arg)*);
+                    res.map(|x| x.into())
+                })
             }
         )+
     };
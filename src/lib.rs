@@ -4,110 +4,328 @@
 #[macro_use]
 mod gen_macro;
 
+mod classes;
+mod dispatch;
 mod stat_funcs;
 mod utils;
 
 use cpython::*;
-use crate::stat_funcs::errors::to_python_result;
-use crate::utils::{extract_ordered_floats, pylist_to_vec};
+use crate::classes::{CountMinSketch, GKSummary, HeavyHitters, HyperLogLog, KllSketch,
+                     P2Quantile, P2Quantiles, Reservoir, RunningStats, TDigest};
+use crate::dispatch::{count_distinct_py, kth_elem_py, mode_py, sample_py, weighted_sample_py};
+use crate::stat_funcs::errors::{to_python_result, without_gil};
+use crate::stat_funcs::{Accumulator, NanOutput, NanPolicy, QuantileMethod};
+use crate::utils::{as_ordered_floats, counts_to_py, extract_samples, for_each_chunk,
+                   into_ordered_floats, is_in_memory, IntoFloats};
 use ordered_float::OrderedFloat;
 
 py_module_initializer!(fast_stat, initfast_stat, PyInit_fast_stat, |py, m| {
-    m.add(py, "mean", py_fn!(py, mean_py(xs: PyObject)))?;
+    m.add(
+        py,
+        "mean",
+        py_fn!(py, mean_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
 
     m.add(
         py,
         "variance",
-        py_fn!(py, variance_py(xs: PyObject)),
+        py_fn!(py, variance_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
     )?;
 
-    m.add(py, "stdev", py_fn!(py, stdev_py(xs: PyObject)))?;
+    m.add(
+        py,
+        "stdev",
+        py_fn!(py, stdev_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
 
     m.add(
         py,
         "pvariance",
-        py_fn!(py, pvariance_py(xs: PyObject)),
+        py_fn!(py, pvariance_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
     )?;
 
-    m.add(py, "pstdev", py_fn!(py, pstdev_py(xs: PyObject)))?;
+    m.add(
+        py,
+        "pstdev",
+        py_fn!(py, pstdev_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
 
     m.add(
         py,
         "harmonic_mean",
-        py_fn!(py, harmonic_mean_py(xs: PyObject)),
+        py_fn!(py, harmonic_mean_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
     )?;
 
-    m.add(py, "median", py_fn!(py, median_py(xs: PyObject)))?;
+    m.add(
+        py,
+        "median",
+        py_fn!(py, median_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
 
     m.add(
         py,
         "median_grouped",
-        py_fn!(py, median_grouped_py(xs: PyObject, interval: usize)),
+        py_fn!(py, median_grouped_py(xs: PyObject, interval: usize,
+                                     nan_policy: NanPolicy = NanPolicy::Propagate)),
     )?;
 
     m.add(
         py,
         "median_low",
-        py_fn!(py, median_low_py(xs: PyObject)),
+        py_fn!(py, median_low_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
     )?;
 
     m.add(
         py,
         "median_high",
-        py_fn!(py, median_high_py(xs: PyObject)),
+        py_fn!(py, median_high_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
+
+    m.add(
+        py,
+        "quantiles",
+        py_fn!(py, quantiles_py(xs: PyObject, n: usize = 4,
+                                method: QuantileMethod = QuantileMethod::Exclusive,
+                                nan_policy: NanPolicy = NanPolicy::Propagate)),
     )?;
 
-    m.add(py, "mode_float", py_fn!(py, mode_float_py(xs: PyObject)))?;
+    m.add(
+        py,
+        "mode",
+        py_fn!(py, mode_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
+
+    m.add(
+        py,
+        "mode_float",
+        py_fn!(py, mode_float_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
     m.add(py, "mode_int", py_fn!(py, mode_int_py(xs: PyObject)))?;
     m.add(py, "mode_uint", py_fn!(py, mode_uint_py(xs: PyObject)))?;
     m.add(py, "mode_str", py_fn!(py, mode_str_py(xs: PyObject)))?;
 
+    m.add(
+        py,
+        "multimode_float",
+        py_fn!(py, multimode_float_py(xs: PyObject, nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
+    m.add(py, "multimode_int", py_fn!(py, multimode_int_py(xs: PyObject)))?;
+    m.add(py, "multimode_uint", py_fn!(py, multimode_uint_py(xs: PyObject)))?;
+    m.add(py, "multimode_str", py_fn!(py, multimode_str_py(xs: PyObject)))?;
+
+    m.add(
+        py,
+        "value_counts_float",
+        py_fn!(py, value_counts_float_py(xs: PyObject, sort: bool = true, top: Option<usize> = None,
+                                         normalize: bool = false,
+                                         nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
+
+    m.add(
+        py,
+        "value_counts_int",
+        py_fn!(py, value_counts_int_py(xs: PyObject, sort: bool = true, top: Option<usize> = None,
+                                       normalize: bool = false)),
+    )?;
+
+    m.add(
+        py,
+        "value_counts_uint",
+        py_fn!(py, value_counts_uint_py(xs: PyObject, sort: bool = true, top: Option<usize> = None,
+                                        normalize: bool = false)),
+    )?;
+
+    m.add(
+        py,
+        "value_counts_str",
+        py_fn!(py, value_counts_str_py(xs: PyObject, sort: bool = true, top: Option<usize> = None,
+                                       normalize: bool = false)),
+    )?;
+
+    m.add(py, "count_distinct", py_fn!(py, count_distinct_py(xs: PyObject)))?;
+
+    m.add(
+        py,
+        "kth_elem",
+        py_fn!(py, kth_elem_py(xs: PyObject, k: usize, seed: Option<u64> = None,
+                               nan_policy: NanPolicy = NanPolicy::Propagate)),
+    )?;
+
     m.add(
         py,
         "kth_elem_float",
-        py_fn!(py, kth_elem_float_py(xs: PyObject, k: usize)),
+        py_fn!(py, kth_elem_float_py(xs: PyObject, k: usize,
+                                     nan_policy: NanPolicy = NanPolicy::Propagate,
+                                     seed: Option<u64> = None)),
     )?;
 
     m.add(
         py,
         "kth_elem_int",
-        py_fn!(py, kth_elem_int_py(xs: PyObject, k: usize)),
+        py_fn!(py, kth_elem_int_py(xs: PyObject, k: usize, seed: Option<u64> = None)),
     )?;
 
     m.add(
         py,
         "kth_elem_uint",
-        py_fn!(py, kth_elem_uint_py(xs: PyObject, k: usize)),
+        py_fn!(py, kth_elem_uint_py(xs: PyObject, k: usize, seed: Option<u64> = None)),
+    )?;
+
+    m.add(
+        py,
+        "kth_elems_float",
+        py_fn!(py, kth_elems_float_py(xs: PyObject, ks: Vec<usize>,
+                                      nan_policy: NanPolicy = NanPolicy::Propagate,
+                                      seed: Option<u64> = None)),
+    )?;
+
+    m.add(
+        py,
+        "kth_elems_int",
+        py_fn!(py, kth_elems_int_py(xs: PyObject, ks: Vec<usize>, seed: Option<u64> = None)),
+    )?;
+
+    m.add(
+        py,
+        "kth_elems_uint",
+        py_fn!(py, kth_elems_uint_py(xs: PyObject, ks: Vec<usize>, seed: Option<u64> = None)),
+    )?;
+
+    m.add(py, "seed", py_fn!(py, seed_py(seed: Option<u64> = None)))?;
+
+    m.add(
+        py,
+        "sample",
+        py_fn!(py, sample_py(xs: PyObject, k: usize, seed: Option<u64> = None)),
+    )?;
+
+    m.add(
+        py,
+        "weighted_sample",
+        py_fn!(py, weighted_sample_py(xs: PyObject, weights: PyObject, k: usize,
+                                      seed: Option<u64> = None)),
     )?;
 
+    m.add_class::<RunningStats>(py)?;
+    m.add_class::<HeavyHitters>(py)?;
+    m.add_class::<CountMinSketch>(py)?;
+    m.add_class::<HyperLogLog>(py)?;
+    m.add_class::<TDigest>(py)?;
+    m.add_class::<KllSketch>(py)?;
+    m.add_class::<P2Quantile>(py)?;
+    m.add_class::<P2Quantiles>(py)?;
+    m.add_class::<GKSummary>(py)?;
+    m.add_class::<Reservoir>(py)?;
+
     Ok(())
 });
 
-gen_wrapper!(variance, (variance_py, [] => f64));
+gen_wrapper!(nan stream variance, RunningStats::variance, (variance_py, [] => f64));
+
+gen_wrapper!(nan stream pvariance, RunningStats::pvariance, (pvariance_py, [] => f64));
+
+gen_wrapper!(nan stream pstdev, RunningStats::pstdev, (pstdev_py, [] => f64));
+
+gen_wrapper!(nan stream stdev, RunningStats::stdev, (stdev_py, [] => f64));
+
+gen_wrapper!(nan stream mean, RunningStats::mean, (mean_py, [] => f64));
 
-gen_wrapper!(pvariance, (pvariance_py, [] => f64));
+gen_wrapper!(nan stream harmonic_mean, HarmonicMean::harmonic_mean,
+            (harmonic_mean_py, [] => f64));
 
-gen_wrapper!(pstdev, (pstdev_py, [] => f64));
+gen_wrapper!(nan mut median, (median_py, [] => f64));
 
-gen_wrapper!(stdev, (stdev_py, [] => f64));
+gen_wrapper!(stream mode, ModeCounter::mode, (mode_str_py, [] => String));
+gen_wrapper!(stream int_mode, ModeCounter::mode,
+            (mode_int_py, [] => i64), (mode_uint_py, [] => u64));
+gen_wrapper!(nan stream ord mode, ModeCounter::mode, (mode_float_py, [] => f64));
 
-gen_wrapper!(mean, (mean_py, [] => f64));
+gen_wrapper!(stream multimode, ModeCounter::multimode,
+            (multimode_str_py, [] => Vec<String>), (multimode_int_py, [] => Vec<i64>),
+            (multimode_uint_py, [] => Vec<u64>));
+gen_wrapper!(nan stream ord multimode, ModeCounter::multimode,
+            (multimode_float_py, [] => Vec<f64>));
 
-gen_wrapper!(harmonic_mean, (harmonic_mean_py, [] => f64));
+gen_wrapper!(stream value_counts, ModeCounter::value_counts,
+            (value_counts_str, [] => Vec<(String, u64)>), (value_counts_int, [] => Vec<(i64, u64)>),
+            (value_counts_uint, [] => Vec<(u64, u64)>));
+// NaN values are counted as a single distinct value unless nan_policy is 'raise' or 'omit'
 
-gen_wrapper!(mut median, (median_py, [] => f64));
+crate fn value_counts_float(py: Python<'_>, xs: PyObject, nan_policy: NanPolicy) ->
+                                                                PyResult<Vec<(f64, u64)>> {
+    if is_in_memory(py, &xs) {
+        let samples = extract_samples::<f64>(py, &xs)?;
+        let ys = samples.as_slice();
+        without_gil(py, || {
+            let ys = nan_policy.apply_counting(ys)?;
+            let res = stat_funcs::value_counts::<OrderedFloat<f64>>(as_ordered_floats(&ys));
+            res.map(|x| x.into_floats())
+        })
+    } else {
+        let mut acc = stat_funcs::ModeCounter::<OrderedFloat<f64>>::default();
+        for_each_chunk::<f64, _>(py, &xs, |chunk| {
+            let chunk = nan_policy.apply_counting(chunk)?;
+            acc.update(as_ordered_floats(&chunk))
+        })?;
+        to_python_result(py, acc.value_counts().map(|x| x.into_floats()))
+    }
+}
 
-gen_wrapper!(mode,
-            (mode_str_py, [] => String), (mode_int_py, [] => i64), (mode_uint_py, [] => u64));
-gen_wrapper!(ord mode, (mode_float_py, [] => f64));
+crate fn value_counts_float_py(py: Python<'_>, xs: PyObject, sort: bool, top: Option<usize>,
+                               normalize: bool, nan_policy: NanPolicy) -> PyResult<PyObject> {
+    counts_to_py(py, value_counts_float(py, xs, nan_policy)?, sort, top, normalize)
+}
 
+crate fn value_counts_int_py(py: Python<'_>, xs: PyObject, sort: bool, top: Option<usize>,
+                             normalize: bool) -> PyResult<PyObject> {
+    counts_to_py(py, value_counts_int(py, xs)?, sort, top, normalize)
+}
 
-gen_wrapper!(mut kth_stat, (kth_elem_float_py, [k::usize] => f64),
-                           (kth_elem_uint_py, [k::usize] => u64),
-                           (kth_elem_int_py, [k::usize] => i64)
+crate fn value_counts_uint_py(py: Python<'_>, xs: PyObject, sort: bool, top: Option<usize>,
+                              normalize: bool) -> PyResult<PyObject> {
+    counts_to_py(py, value_counts_uint(py, xs)?, sort, top, normalize)
+}
+
+crate fn value_counts_str_py(py: Python<'_>, xs: PyObject, sort: bool, top: Option<usize>,
+                             normalize: bool) -> PyResult<PyObject> {
+    counts_to_py(py, value_counts_str(py, xs)?, sort, top, normalize)
+}
+
+// long inputs are processed by radix selection, see stat_funcs::radix_kth_stats_recur
+
+gen_wrapper!(nan mut radix_kth_stat, (kth_elem_float, [k::usize, seed::Option<u64>] => f64));
+gen_wrapper!(mut radix_kth_stat, (kth_elem_uint_py, [k::usize, seed::Option<u64>] => u64),
+                                 (kth_elem_int_py, [k::usize, seed::Option<u64>] => i64)
+            );
+
+gen_wrapper!(nan mut radix_kth_stats,
+            (kth_elems_float, [ks::Vec<usize>, seed::Option<u64>] => Vec<f64>));
+gen_wrapper!(mut radix_kth_stats,
+            (kth_elems_uint_py, [ks::Vec<usize>, seed::Option<u64>] => Vec<u64>),
+            (kth_elems_int_py, [ks::Vec<usize>, seed::Option<u64>] => Vec<i64>)
             );
 
-gen_wrapper!(mut median_grouped, (median_grouped_py, [interval::usize] => f64));
+// seed goes after nan_policy, so that nan_policy may still be passed positionally
+
+crate fn kth_elem_float_py(py: Python<'_>, xs: PyObject, k: usize, nan_policy: NanPolicy,
+                           seed: Option<u64>) -> PyResult<f64> {
+    kth_elem_float(py, xs, k, seed, nan_policy)
+}
+
+crate fn kth_elems_float_py(py: Python<'_>, xs: PyObject, ks: Vec<usize>, nan_policy: NanPolicy,
+                            seed: Option<u64>) -> PyResult<Vec<f64>> {
+    kth_elems_float(py, xs, ks, seed, nan_policy)
+}
+
+crate fn seed_py(py: Python<'_>, seed: Option<u64>) -> PyResult<PyObject> {
+    stat_funcs::set_seed(seed);
+    Ok(py.None())
+}
+
+gen_wrapper!(nan mut median_grouped, (median_grouped_py, [interval::usize] => f64));
+
+gen_wrapper!(nan mut quantiles,
+            (quantiles_py, [n::usize, method::QuantileMethod] => Vec<f64>));
 
-gen_wrapper!(ord mut median_low, (median_low_py, [] => f64));
-gen_wrapper!(ord mut median_high, (median_high_py, [] => f64));
+gen_wrapper!(nan ord mut median_low, (median_low_py, [] => f64));
+gen_wrapper!(nan ord mut median_high, (median_high_py, [] => f64));
@@ -0,0 +1,137 @@
+use super::errors::MyError;
+use super::hashing::{hash_with_seed, StableHash};
+use super::serialize::{ByteReader, ByteWriter};
+
+const TAG: &[u8; 4] = b"CMS1";
+
+/// Count-Min sketch by Cormode and Muthukrishnan, approximate frequencies of items of a stream
+/// are kept in a table of depth rows by width counters, see:
+///
+/// http://dimacs.rutgers.edu/~graham/pubs/papers/cm-full.pdf
+///
+/// Estimates never underestimate true frequency, with probability 1 - exp(-depth) the
+/// overestimation doesn't exceed e * total / width. Counter of each row is chosen by double
+/// hashing, so that only two hashes are computed per item
+#[derive(Clone, Debug)]
+crate struct CountMinSketch {
+    width: usize,
+    depth: usize,
+    seed: u64,
+    total: u64,
+    table: Vec<u64>,
+}
+
+impl CountMinSketch {
+    crate fn new(width: usize, depth: usize, seed: u64) -> Result<Self, MyError> {
+        if width == 0 || depth == 0 {
+            return Err(MyError::CountMinInvalidSize);
+        }
+
+        Ok(CountMinSketch {
+            width,
+            depth,
+            seed,
+            total: 0,
+            table: vec![0; width * depth],
+        })
+    }
+
+    /// Indices of table cells corresponding to the item, one per row
+    #[inline]
+    fn cells<T: StableHash + ?Sized>(&self, x: &T) -> impl Iterator<Item = usize> {
+        let h1 = hash_with_seed(x, self.seed);
+        // second hash must be odd, otherwise rows may repeat for even widths
+        let h2 = hash_with_seed(x, !self.seed) | 1;
+        let width = self.width;
+
+        (0..self.depth).map(move |row| {
+            let h = h1.wrapping_add((row as u64).wrapping_mul(h2));
+            row * width + (h % width as u64) as usize
+        })
+    }
+
+    crate fn add<T: StableHash + ?Sized>(&mut self, x: &T, count: u64) {
+        for cell in self.cells(x) {
+            self.table[cell] = self.table[cell].saturating_add(count);
+        }
+        self.total = self.total.saturating_add(count);
+    }
+
+    crate fn estimate<T: StableHash + ?Sized>(&self, x: &T) -> u64 {
+        self.cells(x).map(|cell| self.table[cell]).min().unwrap_or(0)
+    }
+
+    /// Sketches must have the same dimensions and seed to be merged, the result is the same as
+    /// if all the items were added to a single sketch
+    crate fn merge(&mut self, other: &CountMinSketch) -> Result<(), MyError> {
+        if (self.width, self.depth, self.seed) != (other.width, other.depth, other.seed) {
+            return Err(MyError::IncompatibleSketches);
+        }
+
+        for (a, b) in self.table.iter_mut().zip(&other.table) {
+            *a = a.saturating_add(*b);
+        }
+        self.total = self.total.saturating_add(other.total);
+
+        Ok(())
+    }
+
+    /// Sum of counts of all the items added so far
+    crate fn total(&self) -> u64 {
+        self.total
+    }
+
+    crate fn width(&self) -> usize {
+        self.width
+    }
+
+    crate fn depth(&self) -> usize {
+        self.depth
+    }
+
+    crate fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    crate fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(TAG);
+
+        writer.put_u64(self.width as u64);
+        writer.put_u64(self.depth as u64);
+        writer.put_u64(self.seed);
+        writer.put_u64(self.total);
+
+        for x in &self.table {
+            writer.put_u64(*x);
+        }
+
+        writer.into_bytes()
+    }
+
+    crate fn from_bytes(data: &[u8]) -> Result<Self, MyError> {
+        let mut reader = ByteReader::new(data, TAG)?;
+
+        let width = reader.get_u64()? as usize;
+        let depth = reader.get_u64()? as usize;
+        let seed = reader.get_u64()?;
+        let total = reader.get_u64()?;
+
+        if width == 0 || depth == 0 || reader.remaining() / width < depth {
+            return Err(MyError::InvalidSketchData);
+        }
+
+        let table = (0..width * depth)
+            .map(|_| reader.get_u64())
+            .collect::<Result<Vec<u64>, MyError>>()?;
+
+        reader.finish()?;
+
+        Ok(CountMinSketch {
+            width,
+            depth,
+            seed,
+            total,
+            table,
+        })
+    }
+}
@@ -24,6 +24,58 @@ crate enum MyError {
     NoEnoughDataForPopulationVariance,
     #[fail(display = "mean requires at least one data point")]
     NoEnoughDataForMean,
+    #[fail(display = "min and max require at least one data point")]
+    NoEnoughDataForMinMax,
+    #[fail(
+        display = "index {} is out of range for data of length {}",
+        k, len
+    )]
+    KthOutOfRange { k: usize, len: usize },
+    #[fail(display = "n must be at least 1")]
+    QuantilesInvalidN,
+    #[fail(display = "must have at least one data point")]
+    NoEnoughDataForQuantiles,
+    #[fail(display = "unknown method: {:?}", method)]
+    UnknownQuantileMethod { method: String },
+    #[fail(display = "input data contains NaN values")]
+    NanInData,
+    #[fail(
+        display = "unknown nan_policy: {:?}, expected 'propagate', 'raise' or 'omit'",
+        policy
+    )]
+    UnknownNanPolicy { policy: String },
+    #[fail(display = "k must be at least 1")]
+    HeavyHittersInvalidK,
+    #[fail(display = "sketches with different parameters can't be merged")]
+    IncompatibleSketches,
+    #[fail(display = "width and depth must be at least 1")]
+    CountMinInvalidSize,
+    #[fail(display = "invalid serialized sketch data")]
+    InvalidSketchData,
+    #[fail(display = "precision must be between 4 and 18, got {}", precision)]
+    HyperLogLogInvalidPrecision { precision: i64 },
+    #[fail(display = "compression must be a finite number not less than 1")]
+    TDigestInvalidCompression,
+    #[fail(display = "t-digest accepts only finite numbers")]
+    TDigestNonFiniteValue,
+    #[fail(display = "quantile must be between 0 and 1")]
+    InvalidQuantile,
+    #[fail(display = "k must be at least 8")]
+    KllInvalidK,
+    #[fail(display = "can't mix strings and numbers in one sketch")]
+    MixedTypes,
+    #[fail(display = "at least one quantile must be given")]
+    NoQuantilesGiven,
+    #[fail(display = "epsilon must be between 0 and 1")]
+    GKInvalidEpsilon,
+    #[fail(display = "sample larger than population")]
+    SampleLargerThanPopulation,
+    #[fail(display = "weights must be non-negative finite numbers")]
+    InvalidWeights,
+    #[fail(display = "the number of weights does not match the population")]
+    WeightsLengthMismatch,
+    #[fail(display = "k must be at least 1")]
+    ReservoirInvalidK,
 }
 
 #[inline]
@@ -33,3 +85,14 @@ crate fn to_python_result<T>(py: Python<'_>, res: Result<T, MyError>) -> PyResul
         Ok(x) => Ok(x),
     }
 }
+
+/// Run rust computation with the GIL released, so that other python threads may proceed while
+/// it works, conversion of the result or an error back into python objects takes place after
+/// the GIL has been acquired again
+#[inline]
+crate fn without_gil<T, F>(py: Python<'_>, f: F) -> PyResult<T>
+where
+    F: Send + FnOnce() -> Result<T, MyError>,
+{
+    to_python_result(py, py.allow_threads(f))
+}
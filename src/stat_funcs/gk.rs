@@ -0,0 +1,306 @@
+use super::errors::MyError;
+use super::serialize::{ByteReader, ByteWriter, Serializable};
+
+const TAG: &[u8; 4] = b"GKS2";
+
+/// Summary tuple, v is a value seen in the stream, g is the difference between minimum possible
+/// ranks of v and of the previous tuple value, delta is the difference between maximum and
+/// minimum possible ranks of v
+#[derive(Clone, Debug, PartialEq)]
+struct Tuple<T> {
+    v: T,
+    g: u64,
+    delta: u64,
+}
+
+/// Epsilon-approximate quantile summary by Greenwald and Khanna, see:
+///
+/// http://infolab.stanford.edu/~datar/courses/cs361a/papers/quantiles.pdf
+///
+/// Unlike randomized sketches, the error guarantee is deterministic: rank of a value returned
+/// by query(phi) differs from phi * count by no more than epsilon * count. Tuples are removed
+/// by compress, which runs once per 1 / (2 * epsilon) insertions
+#[derive(Clone, Debug)]
+crate struct GKSummary<T> {
+    epsilon: f64,
+    tuples: Vec<Tuple<T>>,
+    count: u64,
+}
+
+impl<T: PartialOrd + Clone> GKSummary<T> {
+    crate fn new(epsilon: f64) -> Result<Self, MyError> {
+        if !(epsilon > 0.0 && epsilon < 1.0) {
+            return Err(MyError::GKInvalidEpsilon);
+        }
+
+        Ok(GKSummary {
+            epsilon,
+            tuples: Vec::new(),
+            count: 0,
+        })
+    }
+
+    /// Maximum allowed difference between maximum and minimum possible ranks of tuple values
+    #[inline]
+    fn threshold(&self) -> u64 {
+        (2.0 * self.epsilon * self.count as f64).floor() as u64
+    }
+
+    crate fn insert(&mut self, x: T) {
+        let idx = self
+            .tuples
+            .iter()
+            .position(|t| x < t.v)
+            .unwrap_or_else(|| self.tuples.len());
+
+        // new minimum and maximum have exactly known ranks, otherwise g + delta of the new tuple
+        // must not exceed the threshold
+        let delta = if idx == 0 || idx == self.tuples.len() {
+            0
+        } else {
+            self.threshold().saturating_sub(1)
+        };
+
+        self.tuples.insert(idx, Tuple { v: x, g: 1, delta });
+        self.count += 1;
+
+        let period = (1.0 / (2.0 * self.epsilon)).floor().max(1.0) as u64;
+        if self.count % period == 0 {
+            self.compress();
+        }
+    }
+
+    crate fn extend(&mut self, xs: &[T]) {
+        for x in xs {
+            self.insert(x.clone());
+        }
+    }
+
+    /// Remove tuples whose rank ranges can be absorbed by the next tuple without breaking the
+    /// error guarantee, the first and the last tuples are always kept
+    fn compress(&mut self) {
+        let threshold = self.threshold();
+        let mut i = self.tuples.len().saturating_sub(2);
+
+        while i >= 1 {
+            let g = self.tuples[i].g;
+            let next = &mut self.tuples[i + 1];
+
+            if g + next.g + next.delta <= threshold {
+                next.g += g;
+                self.tuples.remove(i);
+            }
+
+            i -= 1;
+        }
+    }
+
+    /// Minimum and maximum possible ranks of each tuple value
+    fn rank_bounds(&self) -> Vec<(u64, u64)> {
+        let mut rmin = 0;
+
+        self.tuples
+            .iter()
+            .map(|t| {
+                rmin += t.g;
+                (rmin, rmin + t.delta)
+            })
+            .collect()
+    }
+
+    /// Combine summaries of disjoint streams, the result keeps the error guarantee of epsilon,
+    /// both summaries must have the same epsilon
+    crate fn merge(&mut self, other: &GKSummary<T>) -> Result<(), MyError> {
+        if self.epsilon != other.epsilon {
+            return Err(MyError::IncompatibleSketches);
+        }
+
+        // rank bounds of a value in the union are sums of its bounds in its own summary and of
+        // bounds of the nearest values of the other summary
+        fn combine<T: Clone>(
+            xs: &[Tuple<T>],
+            xs_bounds: &[(u64, u64)],
+            i: usize,
+            ys_bounds: &[(u64, u64)],
+            j: usize,
+            ys_count: u64,
+        ) -> (T, u64, u64) {
+            let prev_rmin = if j > 0 { ys_bounds[j - 1].0 } else { 0 };
+            let next_rmax = if j < ys_bounds.len() {
+                ys_bounds[j].1 - 1
+            } else {
+                ys_count
+            };
+            (
+                xs[i].v.clone(),
+                xs_bounds[i].0 + prev_rmin,
+                xs_bounds[i].1 + next_rmax,
+            )
+        }
+
+        let (a, b) = (&self.tuples, &other.tuples);
+        let (a_bounds, b_bounds) = (self.rank_bounds(), other.rank_bounds());
+
+        let mut merged = Vec::with_capacity(a.len() + b.len());
+        let (mut i, mut j) = (0, 0);
+
+        while i < a.len() || j < b.len() {
+            if j == b.len() || (i < a.len() && a[i].v <= b[j].v) {
+                merged.push(combine(a, &a_bounds, i, &b_bounds, j, other.count));
+                i += 1;
+            } else {
+                merged.push(combine(b, &b_bounds, j, &a_bounds, i, self.count));
+                j += 1;
+            }
+        }
+
+        let mut prev_rmin = 0;
+        self.tuples = merged
+            .into_iter()
+            .map(|(v, rmin, rmax)| {
+                let t = Tuple {
+                    v,
+                    g: rmin - prev_rmin,
+                    delta: rmax - rmin,
+                };
+                prev_rmin = rmin;
+                t
+            })
+            .collect();
+
+        self.count += other.count;
+        self.compress();
+
+        Ok(())
+    }
+
+    /// Value whose rank differs from phi * count by no more than epsilon * count, 0 and 1
+    /// correspond to exact minimum and maximum, which are always kept as the first and the last
+    /// tuples
+    crate fn query(&self, phi: f64) -> Result<T, MyError> {
+        if !(phi >= 0.0 && phi <= 1.0) {
+            return Err(MyError::InvalidQuantile);
+        } else if self.count == 0 {
+            return Err(MyError::NoEnoughDataForQuantiles);
+        } else if phi == 0.0 {
+            return self.min();
+        }
+
+        let rank = (phi * self.count as f64).ceil();
+        let error = self.epsilon * self.count as f64;
+
+        let bounds = self.rank_bounds();
+        let idx = bounds
+            .iter()
+            .position(|(_, rmax)| *rmax as f64 > rank + error)
+            .unwrap_or_else(|| bounds.len());
+
+        Ok(self.tuples[idx.max(1) - 1].v.clone())
+    }
+
+    /// Estimated fraction of values which are less than or equal to x, it differs from the true
+    /// one by no more than epsilon
+    crate fn rank(&self, x: &T) -> Result<f64, MyError> {
+        if self.count == 0 {
+            return Err(MyError::NoEnoughDataForQuantiles);
+        }
+
+        let bounds = self.rank_bounds();
+        let idx = self.tuples.iter().position(|t| *x < t.v);
+
+        // rank of x is between minimum rank of the last value not greater than x and maximum
+        // rank of the next value minus one
+        let lo = match idx {
+            Some(0) => 0,
+            Some(i) => bounds[i - 1].0,
+            None => self.count,
+        };
+        let hi = idx.map_or(self.count, |i| bounds[i].1 - 1);
+
+        Ok((lo + hi) as f64 / 2.0 / self.count as f64)
+    }
+
+    crate fn count(&self) -> u64 {
+        self.count
+    }
+
+    crate fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    /// Number of stored tuples
+    crate fn size(&self) -> usize {
+        self.tuples.len()
+    }
+
+    crate fn min(&self) -> Result<T, MyError> {
+        self.tuples
+            .first()
+            .map(|t| t.v.clone())
+            .ok_or(MyError::NoEnoughDataForMinMax)
+    }
+
+    crate fn max(&self) -> Result<T, MyError> {
+        self.tuples
+            .last()
+            .map(|t| t.v.clone())
+            .ok_or(MyError::NoEnoughDataForMinMax)
+    }
+}
+
+impl<T: PartialOrd + Clone + Serializable> GKSummary<T> {
+    crate fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(TAG);
+
+        writer.put_f64(self.epsilon);
+        writer.put_u64(self.tuples.len() as u64);
+
+        for t in &self.tuples {
+            t.v.write_to(&mut writer);
+            writer.put_u64(t.g);
+            writer.put_u64(t.delta);
+        }
+
+        writer.into_bytes()
+    }
+
+    crate fn from_bytes(data: &[u8]) -> Result<Self, MyError> {
+        let mut reader = ByteReader::new(data, TAG)?;
+
+        let epsilon = reader.get_f64()?;
+        let mut summary = GKSummary::new(epsilon).map_err(|_| MyError::InvalidSketchData)?;
+
+        let n = reader.get_u64()?;
+
+        for _ in 0..n {
+            let t = Tuple {
+                v: T::read_from(&mut reader)?,
+                g: reader.get_u64()?,
+                delta: reader.get_u64()?,
+            };
+
+            // tuples must be sorted by values, all of which must be comparable (NaN isn't
+            // comparable even to itself)
+            let sorted = summary.tuples.last().map_or(true, |prev| prev.v <= t.v);
+            let comparable = t.v.partial_cmp(&t.v).is_some();
+
+            if !sorted || !comparable || t.g == 0 {
+                return Err(MyError::InvalidSketchData);
+            }
+
+            summary.count = summary
+                .count
+                .checked_add(t.g)
+                .ok_or(MyError::InvalidSketchData)?;
+            summary.tuples.push(t);
+        }
+
+        reader.finish()?;
+
+        if summary.rank_bounds().iter().any(|(_, rmax)| *rmax > summary.count) {
+            return Err(MyError::InvalidSketchData);
+        }
+
+        Ok(summary)
+    }
+}
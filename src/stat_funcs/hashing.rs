@@ -0,0 +1,91 @@
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Seeded 64-bit hasher used by sketches, its output is guaranteed to stay the same between
+/// library versions and platforms, so that serialized sketches remain valid. Values are fed to
+/// it by StableHash, not by std Hash, whose output depends on the platform and the version of
+/// Rust.
+///
+/// Bytes are combined by FNV-1a, the result is then scrambled by splitmix64 finalizer to get
+/// good distribution of all the bits
+crate struct SeededHasher {
+    state: u64,
+}
+
+impl SeededHasher {
+    crate fn new(seed: u64) -> Self {
+        SeededHasher {
+            state: FNV_OFFSET ^ mix(seed),
+        }
+    }
+
+    #[inline]
+    crate fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.state = (self.state ^ u64::from(*b)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Little-endian bytes of x
+    #[inline]
+    crate fn write_u64(&mut self, x: u64) {
+        for i in 0..8 {
+            self.write(&[(x >> (8 * i)) as u8]);
+        }
+    }
+
+    #[inline]
+    crate fn finish(&self) -> u64 {
+        mix(self.state)
+    }
+}
+
+/// Value with a canonical byte encoding fed to SeededHasher: integers of all widths are encoded
+/// as 8 little-endian bytes of their 64-bit value, strings as their utf-8 bytes
+crate trait StableHash {
+    fn stable_hash(&self, hasher: &mut SeededHasher);
+}
+
+macro_rules! impl_stable_hash_int {
+    ($($t:ty),+) => {
+        $(
+            impl StableHash for $t {
+                #[inline]
+                fn stable_hash(&self, hasher: &mut SeededHasher) {
+                    hasher.write_u64(*self as u64);
+                }
+            }
+        )+
+    };
+}
+
+impl_stable_hash_int!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl StableHash for str {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut SeededHasher) {
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl StableHash for String {
+    #[inline]
+    fn stable_hash(&self, hasher: &mut SeededHasher) {
+        self.as_str().stable_hash(hasher);
+    }
+}
+
+/// splitmix64 finalizer
+#[inline]
+crate fn mix(x: u64) -> u64 {
+    let x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    let x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+#[inline]
+crate fn hash_with_seed<T: StableHash + ?Sized>(x: &T, seed: u64) -> u64 {
+    let mut hasher = SeededHasher::new(seed);
+    x.stable_hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,137 @@
+use super::errors::MyError;
+
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hash;
+
+/// Streaming approximation of the most frequent items using Space-Saving algorithm by
+/// Metwally et al., see:
+///
+/// https://www.cs.ucsb.edu/sites/default/files/documents/2005-23.pdf
+///
+/// At most k items are monitored at a time, each one has a counter and a maximum overestimation
+/// error, so that for every monitored item x:
+///
+/// count(x) - error(x) <= frequency(x) <= count(x)
+///
+/// error never exceeds total / k, so every item which occurs more than total / k times is
+/// guaranteed to be monitored
+#[derive(Clone, Debug)]
+crate struct HeavyHitters<T: Eq + Hash + Ord + Clone> {
+    k: usize,
+    total: u64,
+    // item -> (count, error)
+    counters: HashMap<T, (u64, u64)>,
+    // monitored items ordered by their counts, the first one is to be evicted
+    order: BTreeSet<(u64, T)>,
+}
+
+impl<T: Eq + Hash + Ord + Clone> HeavyHitters<T> {
+    crate fn new(k: usize) -> Result<Self, MyError> {
+        if k == 0 {
+            return Err(MyError::HeavyHittersInvalidK);
+        }
+
+        Ok(HeavyHitters {
+            k,
+            total: 0,
+            counters: HashMap::with_capacity(k),
+            order: BTreeSet::new(),
+        })
+    }
+
+    crate fn push(&mut self, x: T) {
+        self.total += 1;
+
+        if let Some((count, _)) = self.counters.get_mut(&x) {
+            self.order.remove(&(*count, x.clone()));
+            *count += 1;
+            self.order.insert((*count, x));
+            return;
+        }
+
+        // new item replaces the one with the smallest count, which becomes its error
+        let min_count = if self.counters.len() < self.k {
+            0
+        } else {
+            let (min_count, evicted) = self.order.iter().next().cloned().unwrap();
+            self.order.remove(&(min_count, evicted.clone()));
+            self.counters.remove(&evicted);
+            min_count
+        };
+
+        self.counters.insert(x.clone(), (min_count + 1, min_count));
+        self.order.insert((min_count + 1, x));
+    }
+
+    crate fn extend(&mut self, xs: &[T]) {
+        for x in xs {
+            self.push(x.clone());
+        }
+    }
+
+    /// Smallest count of a monitored item, any item which is not monitored occurred at most
+    /// this number of times
+    fn min_count(&self) -> u64 {
+        if self.counters.len() < self.k {
+            0
+        } else {
+            self.order.iter().next().map_or(0, |x| x.0)
+        }
+    }
+
+    /// Combine two sketches built on disjoint streams, items missing in one of the sketches are
+    /// assumed to have occurred there as many times as its smallest count, see:
+    ///
+    /// Cafaro et al. "A parallel space saving algorithm for frequent items and the Hurwitz zeta
+    /// distribution", 2016
+    crate fn merge(&mut self, other: &HeavyHitters<T>) -> Result<(), MyError> {
+        if self.k != other.k {
+            return Err(MyError::IncompatibleSketches);
+        }
+
+        let (min_a, min_b) = (self.min_count(), other.min_count());
+
+        let mut merged = self
+            .counters
+            .iter()
+            .map(|(x, &(count, error))| {
+                let (count_b, error_b) = other.counters.get(x).cloned().unwrap_or((min_b, min_b));
+                (x.clone(), (count + count_b, error + error_b))
+            })
+            .collect::<HashMap<T, (u64, u64)>>();
+
+        for (x, &(count, error)) in &other.counters {
+            merged
+                .entry(x.clone())
+                .or_insert((count + min_a, error + min_a));
+        }
+
+        let mut counters = merged.into_iter().collect::<Vec<(T, (u64, u64))>>();
+        counters.sort_by(|a, b| (b.1).0.cmp(&(a.1).0).then_with(|| a.0.cmp(&b.0)));
+        counters.truncate(self.k);
+
+        self.total += other.total;
+        self.order = counters.iter().map(|(x, c)| (c.0, x.clone())).collect();
+        self.counters = counters.into_iter().collect();
+
+        Ok(())
+    }
+
+    /// Monitored items as (item, count, error) triples in descending order of their counts
+    crate fn top(&self) -> Vec<(T, u64, u64)> {
+        self.order
+            .iter()
+            .rev()
+            .map(|(count, x)| (x.clone(), *count, self.counters[x].1))
+            .collect()
+    }
+
+    /// Total number of items pushed so far
+    crate fn count(&self) -> u64 {
+        self.total
+    }
+
+    crate fn k(&self) -> usize {
+        self.k
+    }
+}
@@ -0,0 +1,124 @@
+use super::errors::MyError;
+use super::hashing::{hash_with_seed, StableHash};
+use super::serialize::{ByteReader, ByteWriter};
+
+const TAG: &[u8; 4] = b"HLL1";
+
+crate const MIN_PRECISION: u8 = 4;
+crate const MAX_PRECISION: u8 = 18;
+
+/// HyperLogLog cardinality estimator by Flajolet et al., see:
+///
+/// http://algo.inria.fr/flajolet/Publications/FlFuGaMe07.pdf
+///
+/// Sketch of precision p keeps 2^p one byte registers, relative standard error of the estimate
+/// is about 1.04 / sqrt(2^p). Small cardinalities are estimated by linear counting, 64-bit hashes
+/// make large range correction unnecessary
+#[derive(Clone, Debug)]
+crate struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    /// Precision is accepted as any integer, so that all invalid values give the same error
+    crate fn new(precision: i64) -> Result<Self, MyError> {
+        if precision < i64::from(MIN_PRECISION) || precision > i64::from(MAX_PRECISION) {
+            return Err(MyError::HyperLogLogInvalidPrecision { precision });
+        }
+
+        Ok(HyperLogLog {
+            precision: precision as u8,
+            registers: vec![0; 1 << precision],
+        })
+    }
+
+    crate fn add<T: StableHash + ?Sized>(&mut self, x: &T) {
+        let h = hash_with_seed(x, 0);
+
+        // first p bits choose a register, position of the first set bit of the rest is the rank
+        let idx = (h >> (64 - self.precision)) as usize;
+        let rest = h << self.precision;
+        let rank = (rest.leading_zeros() as u8).min(64 - self.precision) + 1;
+
+        if rank > self.registers[idx] {
+            self.registers[idx] = rank;
+        }
+    }
+
+    /// Sketches must have the same precision to be merged, the result is the same as if all the
+    /// items were added to a single sketch
+    crate fn merge(&mut self, other: &HyperLogLog) -> Result<(), MyError> {
+        if self.precision != other.precision {
+            return Err(MyError::IncompatibleSketches);
+        }
+
+        for (a, b) in self.registers.iter_mut().zip(&other.registers) {
+            *a = (*a).max(*b);
+        }
+
+        Ok(())
+    }
+
+    crate fn cardinality(&self) -> f64 {
+        let m = self.registers.len() as f64;
+
+        let alpha = match self.precision {
+            4 => 0.673,
+            5 => 0.697,
+            6 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let sum = self
+            .registers
+            .iter()
+            .map(|r| (-f64::from(*r)).exp2())
+            .sum::<f64>();
+        let estimate = alpha * m * m / sum;
+
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+
+        if estimate <= 2.5 * m && zeros > 0 {
+            // linear counting
+            m * (m / zeros as f64).ln()
+        } else {
+            estimate
+        }
+    }
+
+    crate fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    crate fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new(TAG);
+
+        writer.put_u64(u64::from(self.precision));
+        writer.put_bytes(&self.registers);
+
+        writer.into_bytes()
+    }
+
+    crate fn from_bytes(data: &[u8]) -> Result<Self, MyError> {
+        let mut reader = ByteReader::new(data, TAG)?;
+
+        let precision = reader.get_u64()?;
+
+        if precision < u64::from(MIN_PRECISION) || precision > u64::from(MAX_PRECISION) {
+            return Err(MyError::InvalidSketchData);
+        }
+
+        let mut sketch = HyperLogLog::new(precision as i64)?;
+        let registers = reader.get_bytes(sketch.registers.len())?;
+
+        if registers.iter().any(|r| *r > 64 - sketch.precision + 1) {
+            return Err(MyError::InvalidSketchData);
+        }
+
+        sketch.registers.copy_from_slice(registers);
+        reader.finish()?;
+
+        Ok(sketch)
+    }
+}
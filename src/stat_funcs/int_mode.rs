@@ -0,0 +1,132 @@
+use super::errors::MyError;
+use super::{unique_mode, PAR_STATS_CHUNK};
+
+use int_hash::IntHashMap;
+use rayon::prelude::*;
+use std::cmp::{max, min};
+use std::fmt::Debug;
+use std::hash::Hash;
+
+// values are counted in a plain vector indexed by (value - min) if the difference between
+// maximum and minimum values doesn't exceed this constant and the data length, one such vector
+// is allocated for each rayon job
+const DIRECT_COUNT_MAX_RANGE: usize = 1 << 20;
+
+/// Integer types which may be counted by int_mode, values are converted to i128 to compute the
+/// range of data without overflows
+crate trait IntKey: Copy + Ord + Hash + Send + Sync + Debug {
+    fn to_i128(self) -> i128;
+    fn from_i128(x: i128) -> Self;
+}
+
+macro_rules! impl_int_key {
+    ($($T: ty),+) => {
+        $(
+            impl IntKey for $T {
+                #[inline]
+                fn to_i128(self) -> i128 {
+                    self as i128
+                }
+
+                #[inline]
+                fn from_i128(x: i128) -> Self {
+                    x as $T
+                }
+            }
+        )+
+    };
+}
+
+impl_int_key!(i64, u64, i32, u32);
+
+fn min_max<T: IntKey>(xs: &[T]) -> (T, T) {
+    let merge = |a: (T, T), b: (T, T)| (min(a.0, b.0), max(a.1, b.1));
+
+    if xs.len() <= PAR_STATS_CHUNK {
+        xs.iter().map(|x| (*x, *x)).fold((xs[0], xs[0]), merge)
+    } else {
+        xs.par_iter().map(|x| (*x, *x)).reduce(|| (xs[0], xs[0]), merge)
+    }
+}
+
+/// Count values in a vector indexed by offset from the minimum value, each rayon job fills its
+/// own vector, vectors are summed up afterwards
+fn direct_counts<T: IntKey>(xs: &[T], lo: i128, size: usize) -> Vec<(T, u64)> {
+    let counts = xs
+        .par_chunks(PAR_STATS_CHUNK)
+        .fold(
+            || vec![0u64; size],
+            |mut acc, chunk| {
+                for x in chunk {
+                    acc[(x.to_i128() - lo) as usize] += 1;
+                }
+                acc
+            },
+        )
+        .reduce(
+            || vec![0u64; size],
+            |mut a, b| {
+                for (x, y) in a.iter_mut().zip(b) {
+                    *x += y;
+                }
+                a
+            },
+        );
+
+    counts
+        .into_iter()
+        .enumerate()
+        .filter(|x| x.1 > 0)
+        .map(|(idx, n)| (T::from_i128(lo + idx as i128), n))
+        .collect()
+}
+
+/// Count values in hash maps using integer hasher, each rayon job fills its own map, maps are
+/// merged afterwards
+fn hash_counts<T: IntKey>(xs: &[T]) -> Vec<(T, u64)> {
+    let counts = xs
+        .par_chunks(PAR_STATS_CHUNK)
+        .fold(IntHashMap::default, |mut acc, chunk| {
+            for x in chunk {
+                (*acc.entry(*x).or_insert(0)) += 1;
+            }
+            acc
+        })
+        .reduce(IntHashMap::default, |a, b| {
+            // insert smaller map into the bigger one
+            let (mut a, b) = if a.len() < b.len() { (b, a) } else { (a, b) };
+            for (x, n) in b {
+                (*a.entry(x).or_insert(0)) += n;
+            }
+            a
+        });
+
+    counts.into_iter().collect()
+}
+
+/// Frequencies of all the distinct values in arbitrary order, counting strategy is chosen
+/// depending on the range of values
+crate fn int_counts<T: IntKey>(xs: &[T]) -> Vec<(T, u64)> {
+    if xs.is_empty() {
+        return Vec::new();
+    }
+
+    let (lo, hi) = min_max(xs);
+    let range = hi.to_i128() - lo.to_i128();
+
+    if range < min(DIRECT_COUNT_MAX_RANGE, xs.len()) as i128 {
+        direct_counts(xs, lo.to_i128(), range as usize + 1)
+    } else {
+        hash_counts(xs)
+    }
+}
+
+/// Same as mode, but specialized for integers, counting is performed in parallel
+crate fn int_mode<T: IntKey>(xs: &[T]) -> Result<T, MyError> {
+    unique_mode(int_counts(xs))
+}
+
+/// Same as count_distinct, but specialized for integers
+crate fn int_count_distinct<T: IntKey>(xs: &[T]) -> Result<usize, MyError> {
+    Ok(int_counts(xs).len())
+}
@@ -0,0 +1,255 @@
+use super::errors::MyError;
+use super::seeded_rng;
+
+use rand::{Rng, XorShiftRng};
+use std::cmp::Ordering;
+use std::mem;
+
+const MIN_K: usize = 8;
+
+// capacity of each compactor is this many times smaller than the capacity of the one above it
+const CAPACITY_RATIO: f64 = 2.0 / 3.0;
+
+/// Upper bound of normalized rank error of a sketch with the given k which holds with probability
+/// 99%, empirical formula from Apache DataSketches
+crate fn kll_rank_error(k: usize) -> f64 {
+    2.296 / (k as f64).powf(0.9723)
+}
+
+#[inline]
+fn compare<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+/// KLL quantiles sketch by Karnin, Lang and Liberty, see:
+///
+/// https://arxiv.org/abs/1603.05346
+///
+/// Items are kept in a hierarchy of compactors, each item stored at level h represents 2^h
+/// items of the original stream. When a compactor gets full, its items are sorted and every
+/// other one of them, starting at random offset, is promoted to the next level.
+///
+/// Normalized rank error doesn't exceed rank_error() with probability 99% for any stream, the
+/// random generator may be seeded to get reproducible results
+#[derive(Clone, Debug)]
+crate struct KllSketch<T> {
+    k: usize,
+    compactors: Vec<Vec<T>>,
+    // total number of stored items and the number of items which triggers compaction
+    size: usize,
+    max_size: usize,
+    count: u64,
+    min: Option<T>,
+    max: Option<T>,
+    rng: XorShiftRng,
+}
+
+impl<T: PartialOrd + Clone> KllSketch<T> {
+    crate fn new(k: usize, seed: Option<u64>) -> Result<Self, MyError> {
+        if k < MIN_K {
+            return Err(MyError::KllInvalidK);
+        }
+
+        let mut sketch = KllSketch {
+            k,
+            compactors: Vec::new(),
+            size: 0,
+            max_size: 0,
+            count: 0,
+            min: None,
+            max: None,
+            rng: seeded_rng(seed),
+        };
+
+        sketch.grow();
+        Ok(sketch)
+    }
+
+    /// Capacity of the compactor at the given level, the top compactor has capacity k
+    fn capacity(&self, level: usize) -> usize {
+        let depth = (self.compactors.len() - level - 1) as i32;
+        ((CAPACITY_RATIO.powi(depth) * self.k as f64).ceil() as usize).max(2)
+    }
+
+    fn grow(&mut self) {
+        self.compactors.push(Vec::new());
+        self.max_size = (0..self.compactors.len()).map(|h| self.capacity(h)).sum();
+    }
+
+    /// Compact the lowest full compactor
+    fn compress(&mut self) {
+        for h in 0..self.compactors.len() {
+            if self.compactors[h].len() >= self.capacity(h) {
+                if h + 1 == self.compactors.len() {
+                    self.grow();
+                }
+
+                let mut level = mem::replace(&mut self.compactors[h], Vec::new());
+                level.sort_by(compare);
+
+                // odd item stays at the current level
+                let odd = level.len() % 2;
+                self.compactors[h].extend(level.drain(..odd));
+
+                let offset = self.rng.gen::<bool>() as usize;
+                let promoted = level.into_iter().skip(offset).step_by(2);
+                self.compactors[h + 1].extend(promoted);
+
+                break;
+            }
+        }
+
+        self.size = self.compactors.iter().map(|c| c.len()).sum();
+    }
+
+    fn update_min_max(&mut self, x: &T) {
+        if self.min.as_ref().map_or(true, |min| x < min) {
+            self.min = Some(x.clone());
+        }
+        if self.max.as_ref().map_or(true, |max| x > max) {
+            self.max = Some(x.clone());
+        }
+    }
+
+    crate fn push(&mut self, x: T) {
+        self.update_min_max(&x);
+
+        self.compactors[0].push(x);
+        self.size += 1;
+        self.count += 1;
+
+        if self.size >= self.max_size {
+            self.compress();
+        }
+    }
+
+    /// Combine sketches built on disjoint streams, both sketches must have the same k
+    crate fn merge(&mut self, other: &KllSketch<T>) -> Result<(), MyError> {
+        if self.k != other.k {
+            return Err(MyError::IncompatibleSketches);
+        }
+
+        while self.compactors.len() < other.compactors.len() {
+            self.grow();
+        }
+
+        for (a, b) in self.compactors.iter_mut().zip(&other.compactors) {
+            a.extend(b.iter().cloned());
+        }
+
+        for x in other.min.iter().chain(other.max.iter()) {
+            self.update_min_max(x);
+        }
+
+        self.count += other.count;
+        self.size = self.compactors.iter().map(|c| c.len()).sum();
+
+        while self.size >= self.max_size {
+            self.compress();
+        }
+
+        Ok(())
+    }
+
+    /// All the stored items along with their weights sorted by items
+    fn weighted_items(&self) -> Vec<(&T, u64)> {
+        let mut items = self
+            .compactors
+            .iter()
+            .enumerate()
+            .flat_map(|(h, c)| c.iter().map(move |x| (x, 1u64 << h)))
+            .collect::<Vec<(&T, u64)>>();
+
+        items.sort_by(|a, b| compare(a.0, b.0));
+        items
+    }
+
+    /// Estimated fraction of items for which le returns true, le must be monotone with respect
+    /// to the ordering of items
+    crate fn rank_by<F: Fn(&T) -> bool>(&self, le: F) -> Result<f64, MyError> {
+        if self.count == 0 {
+            return Err(MyError::NoEnoughDataForQuantiles);
+        }
+
+        let weight = self
+            .compactors
+            .iter()
+            .enumerate()
+            .map(|(h, c)| c.iter().filter(|x| le(x)).count() as u64 * (1 << h))
+            .sum::<u64>();
+
+        Ok(weight as f64 / self.count as f64)
+    }
+
+    /// Estimated fraction of items which are less than or equal to x
+    crate fn rank(&self, x: &T) -> Result<f64, MyError> {
+        self.rank_by(|y| y <= x)
+    }
+
+    /// Estimated item having normalized rank q, i.e. the smallest item such that fraction q of
+    /// items are less than or equal to it, 0 and 1 correspond to exact minimum and maximum
+    crate fn quantile(&self, q: f64) -> Result<T, MyError> {
+        if !(q >= 0.0 && q <= 1.0) {
+            return Err(MyError::InvalidQuantile);
+        }
+
+        let (min, max) = match (&self.min, &self.max) {
+            (Some(min), Some(max)) => (min, max),
+            _ => return Err(MyError::NoEnoughDataForQuantiles),
+        };
+
+        if q == 0.0 {
+            return Ok(min.clone());
+        } else if q == 1.0 {
+            return Ok(max.clone());
+        }
+
+        // weights of stored items sum up to the number of items seen so far
+        let target = q * self.count as f64;
+        let mut cumulative = 0;
+
+        for (x, weight) in self.weighted_items() {
+            cumulative += weight;
+            if cumulative as f64 >= target {
+                return Ok(x.clone());
+            }
+        }
+
+        Ok(max.clone())
+    }
+
+    crate fn count(&self) -> u64 {
+        self.count
+    }
+
+    crate fn min(&self) -> Result<T, MyError> {
+        self.min.clone().ok_or(MyError::NoEnoughDataForMinMax)
+    }
+
+    crate fn max(&self) -> Result<T, MyError> {
+        self.max.clone().ok_or(MyError::NoEnoughDataForMinMax)
+    }
+
+    crate fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Convert items by monotone function f, the result is the same as if converted items were
+    /// pushed into the sketch
+    crate fn map<U, F: Fn(T) -> U>(self, f: F) -> KllSketch<U> {
+        KllSketch {
+            k: self.k,
+            compactors: self
+                .compactors
+                .into_iter()
+                .map(|c| c.into_iter().map(&f).collect())
+                .collect(),
+            size: self.size,
+            max_size: self.max_size,
+            count: self.count,
+            min: self.min.map(&f),
+            max: self.max.map(&f),
+            rng: self.rng,
+        }
+    }
+}
@@ -1,17 +1,51 @@
+mod count_min;
 crate mod errors;
+mod gk;
+mod hashing;
+mod heavy_hitters;
+mod hyperloglog;
+mod int_mode;
+mod kll;
+mod nan_policy;
+mod p2;
+mod quantiles;
+mod radix;
+mod running;
+mod sampling;
+mod serialize;
+mod tdigest;
 
 use self::errors::MyError;
-
+crate use self::count_min::CountMinSketch;
+crate use self::gk::GKSummary;
+crate use self::hashing::{SeededHasher, StableHash};
+crate use self::heavy_hitters::HeavyHitters;
+crate use self::hyperloglog::HyperLogLog;
+crate use self::int_mode::{int_count_distinct, int_counts, int_mode};
+crate use self::kll::{kll_rank_error, KllSketch};
+crate use self::nan_policy::{NanOutput, NanPolicy};
+crate use self::p2::P2Quantiles;
+crate use self::quantiles::{quantiles, QuantileMethod};
+crate use self::radix::RadixKey;
+crate use self::running::RunningStats;
+crate use self::sampling::{sample_indices, weighted_sample_indices, Reservoir};
+crate use self::serialize::{ByteReader, ByteWriter, Serializable};
+crate use self::tdigest::TDigest;
+
+use self::hashing::mix;
+use self::radix::radix_select_stats;
 use super::utils::into_mut_notnans;
 use int_hash::IntHashMap;
-use num::{Float, FromPrimitive, Num};
+use lazy_static::lazy_static;
+use num::{Float, FromPrimitive};
 use rand::{Rng, SeedableRng, XorShiftRng};
 use rayon::prelude::*;
-use std::cmp::{max, min, Reverse};
-use std::collections::HashMap;
+use std::cmp::{max, min, Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::ops::{Add, Div, Mul, Sub};
+use std::ops::{Add, Mul, Sub};
+use std::sync::Mutex;
 use superslice::Ext;
 
 macro_rules! from_unwrap {
@@ -20,14 +54,57 @@ macro_rules! from_unwrap {
     };
 }
 
-// TODO: Make it configurable as function argument?
-// this constant was empirically chosen to make kth_stat algorithm work well on various input
-// data samples
-const KTH_SORT_THRESHOLD: usize = 1000;
+// ranges of kth_stat algorithm not longer than this are sorted directly, partitioning them
+// doesn't pay off
+const KTH_SMALL_RANGE: usize = 16;
+
+// pivots of kth_stat algorithm for ranges at least this long are chosen from a random sample
+const KTH_SAMPLE_THRESHOLD: usize = 600;
+
+// number of bad splits in a row after which kth_stat algorithm switches to median of medians
+const KTH_BAD_SPLITS: usize = 3;
+
+// inputs at least this long are processed by radix selection if their elements have radix keys
+const RADIX_SELECT_THRESHOLD: usize = 1 << 14;
+
+// inputs longer than this are split into chunks of the same size, statistics of each chunk are
+// computed in parallel and then merged
+const PAR_STATS_CHUNK: usize = 1 << 16;
+
+lazy_static! {
+    // global seed of random pivots chosen by selection algorithms, see set_seed
+    static ref SEED: Mutex<Option<u64>> = Mutex::new(None);
+}
+
+/// Set global seed of selection algorithms, so that kth_stat and all the functions based on it
+/// choose the same pivots on each call and their results and running times are reproducible.
+/// None restores seeding from thread_rng, seeds passed to the functions directly take precedence
+crate fn set_seed(seed: Option<u64>) {
+    // the seed is a plain value, so it is valid even if another thread panicked holding the lock
+    *SEED.lock().unwrap_or_else(|err| err.into_inner()) = seed;
+}
+
+crate fn global_seed() -> Option<u64> {
+    *SEED.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// XorShift generator initialized by the given seed, or by thread_rng if there is no seed
+crate fn seeded_rng(seed: Option<u64>) -> XorShiftRng {
+    match seed {
+        None => XorShiftRng::from_seed(rand::thread_rng().gen()),
+        Some(seed) => {
+            // xorshift state must not be all zeros, mix is a bijection which maps only zero to
+            // zero, so at least one of two consecutive seeds gives nonzero value
+            let (a, b) = (mix(seed), mix(seed.wrapping_add(1)));
+            XorShiftRng::from_seed([a as u32, (a >> 32) as u32, b as u32, (b >> 32) as u32])
+        }
+    }
+}
 
+/// Generator of random pivot indices, the global seed is used if seed is not given
 #[inline]
-fn init_rand() -> impl FnMut(usize, usize) -> usize {
-    let mut rng: XorShiftRng = XorShiftRng::from_seed(rand::thread_rng().gen());
+fn init_rand(seed: Option<u64>) -> impl FnMut(usize, usize) -> usize {
+    let mut rng = seeded_rng(seed.or_else(global_seed));
     move |from: usize, to: usize| -> usize {
         if from == to {
             0
@@ -37,63 +114,237 @@ fn init_rand() -> impl FnMut(usize, usize) -> usize {
     }
 }
 
-crate fn mode<T: Eq + Ord + Clone + Hash + Debug>(xs: &[T]) -> Result<T, MyError> {
-    if xs.is_empty() {
+/// Statistic which is computed in a single pass over the data, so that the data may be fed
+/// chunk by chunk without keeping all of it in memory
+crate trait Accumulator<T>: Default {
+    fn update(&mut self, xs: &[T]) -> Result<(), MyError>;
+}
+
+/// Pick the most common value out of (value, frequency) pairs, error is returned if there are
+/// several values with the same highest frequency
+crate fn unique_mode<T, I>(pairs: I) -> Result<T, MyError>
+where
+    I: IntoIterator<Item = (T, u64)>,
+{
+    // sort modes by their frequencies
+    let mut tmp = pairs.into_iter().collect::<Vec<(T, u64)>>();
+
+    if tmp.is_empty() {
         return Err(MyError::NoModeEmptyData);
     }
 
-    // create mapping from elements to their frequencies
-    let pairs = xs.into_iter().fold(HashMap::new(), |mut acc, e| {
-        (*acc.entry(e).or_insert(0)) += 1;
-        acc
-    });
-
-    // sort modes by their frequencies
-    let mut tmp = pairs.into_iter().collect::<Vec<(&T, u64)>>();
     tmp.sort_by_key(|x| Reverse(x.1));
 
     // first element must be mode element
-    let (mode, mode_val) = tmp[0];
+    let mode_val = tmp[0].1;
 
     // count number of elements with the same frequency as the mode element
-    let modes = tmp.into_iter().take_while(|x| x.1 == mode_val).count();
+    let modes = tmp.iter().take_while(|x| x.1 == mode_val).count();
 
     match modes {
         // one unique mode found
-        1 => Ok(mode.clone()),
+        1 => Ok(tmp.swap_remove(0).0),
         // many modes with equal frequencies found
         _ => Err(MyError::NoUniqueMode { modes }),
     }
 }
 
-crate fn harmonic_mean<T>(xs: &[T]) -> Result<T, MyError>
+/// Order (value, frequency, first occurrence) triples by their first occurrence
+fn in_first_seen_order<T, I>(triples: I) -> Vec<(T, u64)>
 where
-    T: PartialOrd + Float + Debug,
+    I: IntoIterator<Item = (T, u64, usize)>,
 {
-    if xs.is_empty() {
-        return Err(MyError::HarmonicNoDataPoints);
+    let mut tmp = triples.into_iter().collect::<Vec<(T, u64, usize)>>();
+    tmp.sort_by_key(|x| x.2);
+
+    tmp.into_iter().map(|x| (x.0, x.1)).collect()
+}
+
+/// Pick all the values having the highest frequency out of (value, frequency, first occurrence)
+/// triples, values are returned in order of their first occurrence
+fn all_modes<T, I>(triples: I) -> Vec<T>
+where
+    I: IntoIterator<Item = (T, u64, usize)>,
+{
+    let mut tmp = triples.into_iter().collect::<Vec<(T, u64, usize)>>();
+
+    let mode_val = tmp.iter().map(|x| x.1).max().unwrap_or(0);
+    tmp.retain(|x| x.1 == mode_val);
+
+    in_first_seen_order(tmp).into_iter().map(|x| x.0).collect()
+}
+
+/// Create mapping from elements to their frequencies and first occurrences
+fn count_values<T: Eq + Hash>(xs: &[T]) -> HashMap<&T, (u64, usize)> {
+    xs.into_iter()
+        .enumerate()
+        .fold(HashMap::new(), |mut acc, (idx, e)| {
+            (*acc.entry(e).or_insert((0, idx))).0 += 1;
+            acc
+        })
+}
+
+crate fn mode<T: Eq + Ord + Clone + Hash + Debug>(xs: &[T]) -> Result<T, MyError> {
+    // create mapping from elements to their frequencies
+    let pairs = xs.into_iter().fold(HashMap::new(), |mut acc, e| {
+        (*acc.entry(e).or_insert(0)) += 1;
+        acc
+    });
+
+    unique_mode(pairs).map(|x| x.clone())
+}
+
+/// Return all the most common values in order of their first occurrence, empty data has no
+/// modes
+crate fn multimode<T: Eq + Clone + Hash + Debug>(xs: &[T]) -> Result<Vec<T>, MyError> {
+    let modes = all_modes(count_values(xs).into_iter().map(|(x, (n, idx))| (x, n, idx)));
+
+    Ok(modes.into_iter().cloned().collect())
+}
+
+/// Frequencies of all the distinct values as (value, frequency) pairs in order of the first
+/// occurrence of each value
+crate fn value_counts<T>(xs: &[T]) -> Result<Vec<(T, u64)>, MyError>
+where
+    T: Eq + Clone + Hash + Debug,
+{
+    let triples = count_values(xs).into_iter().map(|(x, (n, idx))| (x, n, idx));
+    let counts = in_first_seen_order(triples);
+
+    Ok(counts.into_iter().map(|(x, n)| (x.clone(), n)).collect())
+}
+
+/// Keep at most `top` of the most frequent (value, frequency) pairs out of pairs given in order of
+/// first occurrence, values having equal frequencies are taken in order of first occurrence.
+///
+/// Pairs are sorted by descending frequency if `sort` is set, otherwise the original order is
+/// kept
+crate fn most_common<T>(counts: Vec<(T, u64)>, top: Option<usize>, sort: bool) -> Vec<(T, u64)> {
+    let mut tmp = counts
+        .into_iter()
+        .enumerate()
+        .collect::<Vec<(usize, (T, u64))>>();
+
+    if sort || top.is_some() {
+        // sort is stable, so the order of first occurrence is preserved for equal frequencies
+        tmp.sort_by_key(|x| Reverse((x.1).1));
     }
 
-    if xs.len() == 1 {
-        // special case for a list of 1 element, we just return the value itself, because
-        // 1/(1/x) == x
-        return Ok(xs[0]);
+    if let Some(top) = top {
+        tmp.truncate(top);
     }
 
-    let result = xs.into_iter().try_fold((T::zero(), T::zero()), |acc, e| {
-        if e >= &T::zero() {
-            Some((acc.0 + e.recip(), acc.1 + T::one()))
-        } else {
-            None
+    if !sort {
+        tmp.sort_by_key(|x| x.0);
+    }
+
+    tmp.into_iter().map(|x| x.1).collect()
+}
+
+/// Exact number of distinct values
+crate fn count_distinct<T: Eq + Hash + Debug>(xs: &[T]) -> Result<usize, MyError> {
+    Ok(xs.iter().collect::<HashSet<&T>>().len())
+}
+
+/// Streaming version of mode, it keeps frequency and the first occurrence of each distinct
+/// value seen so far
+crate struct ModeCounter<T: Eq + Hash> {
+    counts: HashMap<T, (u64, usize)>,
+    seen: usize,
+}
+
+impl<T: Eq + Hash> Default for ModeCounter<T> {
+    fn default() -> Self {
+        ModeCounter {
+            counts: HashMap::new(),
+            seen: 0,
         }
-    });
+    }
+}
 
-    match result {
-        Some((sum, len)) => Ok(len / sum),
-        None => Err(MyError::HarmonicNegatives),
+impl<T: Eq + Hash + Clone> Accumulator<T> for ModeCounter<T> {
+    fn update(&mut self, xs: &[T]) -> Result<(), MyError> {
+        for x in xs {
+            (*self.counts.entry(x.clone()).or_insert((0, self.seen))).0 += 1;
+            self.seen += 1;
+        }
+        Ok(())
     }
 }
 
+impl<T: Eq + Hash + Clone> ModeCounter<T> {
+    crate fn mode(&self) -> Result<T, MyError> {
+        unique_mode(self.counts.iter().map(|(x, (n, _))| (x, *n))).map(|x| x.clone())
+    }
+
+    crate fn multimode(&self) -> Result<Vec<T>, MyError> {
+        let modes = all_modes(self.counts.iter().map(|(x, (n, idx))| (x, *n, *idx)));
+
+        Ok(modes.into_iter().cloned().collect())
+    }
+
+    crate fn value_counts(&self) -> Result<Vec<(T, u64)>, MyError> {
+        let counts = in_first_seen_order(self.counts.iter().map(|(x, (n, idx))| (x, *n, *idx)));
+
+        Ok(counts.into_iter().map(|(x, n)| (x.clone(), n)).collect())
+    }
+}
+
+/// Streaming harmonic mean, keeps the sum of reciprocals of all the values seen so far
+crate struct HarmonicMean<T> {
+    count: usize,
+    first: T,
+    recip_sum: T,
+}
+
+impl<T: Float> Default for HarmonicMean<T> {
+    fn default() -> Self {
+        HarmonicMean {
+            count: 0,
+            first: T::zero(),
+            recip_sum: T::zero(),
+        }
+    }
+}
+
+impl<T: Float> Accumulator<T> for HarmonicMean<T> {
+    fn update(&mut self, xs: &[T]) -> Result<(), MyError> {
+        if xs.iter().any(|x| x < &T::zero()) {
+            return Err(MyError::HarmonicNegatives);
+        }
+
+        if self.count == 0 && !xs.is_empty() {
+            self.first = xs[0];
+        }
+
+        self.count += xs.len();
+        self.recip_sum = xs.iter().fold(self.recip_sum, |acc, x| acc + x.recip());
+
+        Ok(())
+    }
+}
+
+impl<T: Float> HarmonicMean<T> {
+    crate fn harmonic_mean(&self) -> Result<T, MyError> {
+        match self.count {
+            0 => Err(MyError::HarmonicNoDataPoints),
+            // special case for a list of 1 element, we just return the value itself, because
+            // 1/(1/x) == x
+            1 => Ok(self.first),
+            n => Ok(from_unwrap!(T, n) / self.recip_sum),
+        }
+    }
+}
+
+crate fn harmonic_mean<T>(xs: &[T]) -> Result<T, MyError>
+where
+    T: PartialOrd + Float + Debug,
+{
+    let mut acc = HarmonicMean::default();
+    acc.update(xs)?;
+    acc.harmonic_mean()
+}
+
 #[inline]
 fn get_median_pair<'a, T: 'a>(r: &'a IntHashMap<usize, T>) -> (&'a T, &'a T) {
     let v = r.values().collect::<Vec<&T>>();
@@ -102,7 +353,7 @@ fn get_median_pair<'a, T: 'a>(r: &'a IntHashMap<usize, T>) -> (&'a T, &'a T) {
 
 crate fn median<T>(xs: &mut [T]) -> Result<T, MyError>
 where
-    T: Copy + PartialOrd + Add + Send + Debug + Float,
+    T: Copy + PartialOrd + Add + Send + Debug + Float + RadixKey,
 {
     let n = xs.len();
 
@@ -113,35 +364,35 @@ where
     let med_idx = n / 2;
 
     if n % 2 == 0 {
-        let r = kth_stats_recur(xs, &mut [med_idx - 1, med_idx]);
+        let r = radix_kth_stats_recur(xs, &mut [med_idx - 1, med_idx], None);
         let (a, b) = get_median_pair(&r);
         Ok((*a + *b) / from_unwrap!(T, 2.0))
     } else {
-        kth_stat(xs, med_idx)
+        radix_kth_stat(xs, med_idx, None)
     }
 }
 
 fn median_low_high<T>(xs: &mut [T], f: fn(T, T) -> T) -> Result<T, MyError>
 where
-    T: Copy + Ord + Send + Debug,
+    T: Copy + Ord + Send + Debug + RadixKey,
 {
     let med_idx = xs.len() / 2;
 
     if xs.len() % 2 == 0 {
-        let r = kth_stats_recur(xs, &mut [med_idx - 1, med_idx]);
+        let r = radix_kth_stats_recur(xs, &mut [med_idx - 1, med_idx], None);
         let (a, b) = get_median_pair(&r);
         Ok(f(*a, *b))
     } else {
-        kth_stat(xs, med_idx)
+        radix_kth_stat(xs, med_idx, None)
     }
 }
 
-crate fn median_low<T: Copy + Ord + Send + Debug>(ys: &mut [T]) -> Result<T, MyError> {
+crate fn median_low<T: Copy + Ord + Send + Debug + RadixKey>(ys: &mut [T]) -> Result<T, MyError> {
     // Helper function
     median_low_high(ys, min)
 }
 
-crate fn median_high<T: Copy + Ord + Send + Debug>(ys: &mut [T]) -> Result<T, MyError> {
+crate fn median_high<T: Copy + Ord + Send + Debug + RadixKey>(ys: &mut [T]) -> Result<T, MyError> {
     // Helper function
     median_low_high(ys, max)
 }
@@ -190,124 +441,60 @@ where
     Ok(notnan_value.into_inner())
 }
 
-/// Naive implementations of variance/mean computation suffer from a lack of precision
-/// therefor more advanced and much more accurate technique will be used, see:
+/// Accumulate running statistics of the whole input, large inputs are processed in parallel.
 ///
-/// https://math.stackexchange.com/questions/20593/calculate-variance-from-a-stream-of-sample-values
-/// https://www.johndcook.com/blog/standard_deviation/
-///
-/// for details
-#[inline]
-#[allow(unused_mut)]
-fn running_stat<T>() -> impl FnMut(T) -> (T, T)
+/// Partial results are merged in the same order regardless of the number of threads, so the
+/// result is reproducible and differs from the sequential path by rounding errors only
+crate fn running_stats<T>(xs: &[T]) -> RunningStats<T>
 where
-    T: Num + Copy + Debug + FromPrimitive + Mul<T, Output = T> + Div<T, Output = T> +
-    Add<T, Output = T>,
+    T: Float + FromPrimitive + Send + Sync + Debug,
 {
-    let mut m_n = 1;
-
-    let mut old_m = T::zero();
-    let mut old_s = T::zero();
-
-    move |x: T| {
-        let new_m;
-        let new_s;
-
-        if m_n == 1 {
-            new_s = T::zero();
-            old_s = T::zero();
-
-            old_m = x;
-            new_m = x;
-        } else {
-            let common_diff = x - old_m;
-
-            new_m = old_m + common_diff / T::from_usize(m_n).unwrap();
-            new_s = old_s + common_diff * (x - new_m);
-
-            old_m = new_m;
-            old_s = new_s;
-        }
-
-        m_n += 1;
-
-        (new_m, new_s)
+    if xs.len() <= PAR_STATS_CHUNK {
+        return RunningStats::from(xs);
     }
+
+    xs.par_chunks(PAR_STATS_CHUNK)
+        .map(RunningStats::from)
+        .collect::<Vec<RunningStats<T>>>()
+        .into_iter()
+        .fold(RunningStats::new(), |acc, x| acc + x)
 }
 
 /// Return the sample variance of input data
 crate fn variance<T>(xs: &[T]) -> Result<T, MyError>
 where
-    T: Float + FromPrimitive + Debug + Mul<T, Output = T> + Div<T, Output = T> + Add<T, Output = T>,
+    T: Float + FromPrimitive + Send + Sync + Debug,
 {
-    if xs.len() < 2 {
-        Err(MyError::NoEnoughDataForVariance)
-    } else {
-        let mut push_one = running_stat();
-        let mut res = (T::zero(), T::zero());
-
-        for x in xs {
-            res = push_one(*x);
-        }
-
-        Ok(res.1 / T::from_usize(xs.len() - 1).unwrap())
-    }
+    running_stats(xs).variance()
 }
 
 /// Return the population variance of input data
 crate fn pvariance<T>(xs: &[T]) -> Result<T, MyError>
 where
-    T: Float + FromPrimitive + Debug + Mul<T, Output = T> + Div<T, Output = T> + Add<T, Output = T>,
+    T: Float + FromPrimitive + Send + Sync + Debug,
 {
-    if xs.is_empty() {
-        Err(MyError::NoEnoughDataForPopulationVariance)
-    } else {
-        let mut push_one = running_stat();
-        let mut res = (T::zero(), T::zero());
-
-        for x in xs.iter() {
-            res = push_one(*x);
-        }
-
-        Ok(res.1 / T::from_usize(xs.len()).unwrap())
-    }
+    running_stats(xs).pvariance()
 }
 
 crate fn stdev<T>(xs: &[T]) -> Result<T, MyError>
 where
-    T: Float + FromPrimitive + Debug + Mul<T, Output = T> + Div<T, Output = T> + Add<T, Output = T>,
+    T: Float + FromPrimitive + Send + Sync + Debug,
 {
-    let res = variance(xs)?;
-    // variance can't be a negative value no additional checks needed
-    Ok(res.sqrt())
+    running_stats(xs).stdev()
 }
 
 crate fn pstdev<T>(xs: &[T]) -> Result<T, MyError>
 where
-    T: Float + FromPrimitive + Debug + Mul<T, Output = T> + Div<T, Output = T> + Add<T, Output = T>,
+    T: Float + FromPrimitive + Send + Sync + Debug,
 {
-    let res = pvariance(xs)?;
-    // pvariance can't be a negative value no additional checks needed
-    Ok(res.sqrt())
+    running_stats(xs).pstdev()
 }
 
 crate fn mean<T>(xs: &[T]) -> Result<T, MyError>
 where
-    T: Num + Copy + FromPrimitive + Debug + Mul<T, Output = T> + Div<T, Output = T> +
-    Add<T, Output = T>,
+    T: Float + FromPrimitive + Send + Sync + Debug,
 {
-    if xs.is_empty() {
-        Err(MyError::NoEnoughDataForMean)
-    } else {
-        let mut push_one = running_stat();
-        let mut res = (T::zero(), T::zero());
-
-        for x in xs.iter() {
-            res = push_one(*x);
-        }
-
-        Ok(res.0)
-    }
+    running_stats(xs).mean()
 }
 
 /// Partition input slice xs in-place, such that elements smaller than the pivot are at the
@@ -354,26 +541,135 @@ fn partition<T: Copy + PartialOrd + Debug>(
     i
 }
 
+/// Move elements equal to the pivot element to the beginning of range [start, end) which has no
+/// elements smaller than the pivot, index of the first element bigger than the pivot is returned
+fn group_equal<T: Copy + PartialOrd>(
+    xs: &mut [T],
+    pivot_elem: T,
+    start: usize,
+    end: usize,
+) -> usize {
+    let mut i = start;
+
+    for j in start..end {
+        if xs[j] <= pivot_elem {
+            xs.swap(i, j);
+            i += 1;
+        }
+    }
+
+    i
+}
+
+/// Index of a pivot having at least 3/10 of elements of range [left, right) on each side, so
+/// that partitioning around it always shrinks the range by a constant factor.
+///
+/// Medians of groups of five elements are moved to the beginning of the range and their median
+/// is found recursively
+fn median_of_medians<T: Copy + PartialOrd + Send + Debug>(
+    rand_range: &mut impl FnMut(usize, usize) -> usize,
+    xs: &mut [T],
+    left: usize,
+    right: usize,
+) -> usize {
+    let mut groups = 0;
+
+    for start in (left..right).step_by(5) {
+        let end = min(start + 5, right);
+        xs[start..end].sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // medians are moved to the part of the range which has been already processed
+        xs.swap(left + groups, start + (end - start) / 2);
+        groups += 1;
+    }
+
+    let mid = left + groups / 2;
+    kth_stat_helper(rand_range, xs, &[mid], left, left + groups, 0);
+
+    mid
+}
+
+/// Partition range [start, end) into three parts: elements smaller than lo, elements between lo
+/// and hi inclusive and elements bigger than hi, bounds of the middle part are returned
+fn partition_pair<T: Copy + PartialOrd>(
+    xs: &mut [T],
+    lo: T,
+    hi: T,
+    start: usize,
+    end: usize,
+) -> (usize, usize) {
+    let (mut i, mut j, mut k) = (start, start, end);
+
+    while j < k {
+        if xs[j] < lo {
+            xs.swap(i, j);
+            i += 1;
+            j += 1;
+        } else if xs[j] > hi {
+            k -= 1;
+            xs.swap(j, k);
+        } else {
+            j += 1;
+        }
+    }
+
+    (i, k)
+}
+
+/// Pair of pivots which are likely to surround the element of rank k closely, chosen by
+/// Floyd-Rivest algorithm, see:
+///
+/// https://doi.org/10.1145/360680.360691
+///
+/// A random sample of about n^(2/3) elements is moved to the beginning of the range and two
+/// elements of the sample with ranks slightly below and above the expected rank of k in the
+/// sample are selected. The part between the pivots is short, so most of the elements are
+/// discarded by a single partition
+fn sample_pivots<T: Copy + PartialOrd + Send + Debug>(
+    rand_range: &mut impl FnMut(usize, usize) -> usize,
+    xs: &mut [T],
+    k: usize,
+    left: usize,
+    right: usize,
+    budget: usize,
+) -> (T, T) {
+    let n = (right - left) as f64;
+    let z = n.ln();
+    let sample_len = (0.5 * (2.0 * z / 3.0).exp()).floor();
+    let sd = 0.5 * (z * sample_len * (n - sample_len) / n).sqrt();
+    let rank = (k - left) as f64 * sample_len / n;
+
+    let sample_len = sample_len as usize;
+    for i in left..left + sample_len {
+        let j = rand_range(i, right);
+        xs.swap(i, j);
+    }
+
+    let lo = left + (rank - sd).max(0.0) as usize;
+    let hi = left + min((rank + sd) as usize, sample_len - 1);
+    let found = kth_stat_helper(rand_range, xs, &[lo, hi], left, left + sample_len, budget);
+
+    (found[&lo], found[&hi])
+}
+
 fn kth_stat_helper<T: Copy + PartialOrd + Send + Debug>(
     rand_range: &mut impl FnMut(usize, usize) -> usize,
     xs: &mut [T],
-    ks: &mut Vec<usize>,
+    ks: &[usize],
     left: usize,
     right: usize,
-    need_sort: bool,
+    budget: usize,
 ) -> IntHashMap<usize, T> {
-    let empty_hash = IntHashMap::default();
+    let mut found = IntHashMap::default();
 
     if left >= right || ks.is_empty() {
-        return empty_hash;
+        return found;
     }
 
-    if need_sort {
+    if right - left <= KTH_SMALL_RANGE {
         // sort selected array part and choose elements we need
         let ys = &mut xs[left..right];
-        ys.par_sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
-
-        let mut found = empty_hash;
+        ys.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap());
 
         for elem in ks {
             found.insert(*elem, ys[*elem - left]);
@@ -382,93 +678,93 @@ fn kth_stat_helper<T: Copy + PartialOrd + Send + Debug>(
         return found;
     }
 
-    // choose random pivot point
-    let pivot_idx = rand_range(left, right);
-
-    // partition an array into two halves, one consists of all elements less than
-    // the pivot and another one consists of all elements bigger than the pivot
-    let real_idx = partition(xs, pivot_idx, left, right);
-
-    // assess how good an array was partitioned by analyzing sizes of its left and right half
-    let left_len = real_idx - left;
-    let right_len = right - real_idx;
-
-    // compare two halves relative size
-    let need_sort =
-        // right_len / left_len <= THRESHOLD <=> THRESHOLD * right_len <= left_len
-        if left_len >= right_len && (KTH_SORT_THRESHOLD * right_len <= left_len) {
-            true
+    // partition an array into three parts, elements less than the pivots, elements between them
+    // and elements bigger than the pivots. Long ranges are partitioned around a pair of pivots
+    // close to the middle one of ks, otherwise there is a single pivot, which is chosen randomly
+    // or, once the budget of bad splits is spent, by median of medians which is slower on
+    // average but guarantees linear time. Elements equal to a single pivot are grouped
+    // together, so that repeated values don't slow down the search
+    let (mid_start, mid_end, all_equal) =
+        if budget > 0 && right - left >= KTH_SAMPLE_THRESHOLD {
+            let k = ks[ks.len() / 2];
+            let (lo, hi) = sample_pivots(rand_range, xs, k, left, right, budget);
+            let (mid_start, mid_end) = partition_pair(xs, lo, hi, left, right);
+
+            (mid_start, mid_end, lo == hi)
         } else {
-            // left_len / right_len <= THRESHOLD <=> THRESHOLD * left_len <= right_len
-            KTH_SORT_THRESHOLD * left_len <= right_len
+            let pivot_idx = if budget > 0 {
+                rand_range(left, right)
+            } else {
+                median_of_medians(rand_range, xs, left, right)
+            };
+            let pivot_elem = xs[pivot_idx];
+            let mid_start = partition(xs, pivot_idx, left, right);
+
+            (mid_start, group_equal(xs, pivot_elem, mid_start + 1, right), true)
         };
 
-    let ks_len = ks.len();
-    let mut found = empty_hash;
-
     // tricky part, ks - is a sorted array of statistics that we want to
     // find, for example [10, 30, 50, 70, 99, 150], then we will use binary search to
-    // figure out position of pivot element in the ks list
-    let k_idx = match ks.binary_search(&real_idx) {
-        Ok(k_idx) => {
-            found.insert(ks.remove(k_idx), xs[real_idx]);
-            k_idx
-        }
-        Err(k_idx) => k_idx,
+    // split it into statistics which are in each of the parts, no copies are needed
+    let lo = match ks.binary_search(&mid_start) {
+        Ok(idx) | Err(idx) => idx,
     };
-
-    if k_idx > 0 && k_idx < ks_len {
-        // if index of pivot element was somewhere inside of ks list, we need 2 recursive calls
-        // one to find all the elements lesser than the pivot element and another one to find
-        // all the elements bigger than the pivot element
-        let (ks_left, ks_right) = ks.split_at(k_idx);
-
-        found.extend(kth_stat_helper(
-            rand_range,
-            xs,
-            &mut ks_left.to_vec(),
-            left,
-            real_idx,
-            need_sort,
-        ));
-        found.extend(kth_stat_helper(
-            rand_range,
-            xs,
-            &mut ks_right.to_vec(),
-            real_idx + 1,
-            right,
-            need_sort,
-        ));
-    } else if k_idx == 0 {
-        // if the leftmost element of ks was found only one recursive call is required, because
-        // it is guaranteed that there are no elements with the position smaller than k_idx
-        found.extend(kth_stat_helper(
-            rand_range,
-            xs,
-            ks,
-            real_idx + 1,
-            right,
-            need_sort,
-        ));
-    } else if k_idx == ks_len {
-        // if the rightmost element of ks was found only one recursive call is required because
-        // it is guaranteed that there are no elements with the position bigger than k_idx
-        found.extend(kth_stat_helper(
-            rand_range, xs, ks, left, real_idx, need_sort,
-        ));
+    let hi = match ks.binary_search(&mid_end) {
+        Ok(idx) | Err(idx) => idx,
     };
 
+    let parts = [
+        (left, mid_start, &ks[..lo]),
+        (mid_start, mid_end, &ks[lo..hi]),
+        (mid_end, right, &ks[hi..]),
+    ];
+
+    for &(start, end, part_ks) in &parts {
+        if all_equal && start == mid_start {
+            for k in part_ks {
+                found.insert(*k, xs[mid_start]);
+            }
+            continue;
+        }
+
+        // a split is bad if a part gets all the statistics and more than 7/8 of elements, the
+        // budget is restored by a good split, once it is spent it is never restored, so that
+        // the rest of the range is processed by median of medians
+        let bad = part_ks.len() == ks.len() && 8 * (end - start) > 7 * (right - left);
+        let budget = if budget == 0 {
+            0
+        } else if bad {
+            budget - 1
+        } else {
+            KTH_BAD_SPLITS
+        };
+
+        found.extend(kth_stat_helper(rand_range, xs, part_ks, start, end, budget));
+    }
+
     found
 }
 
-/// Kth statistic works in amortized linear time O(n), the worst case will still be O(n^2).
+/// Kth statistic works in linear time O(n) even in the worst case, it is an introselect:
+///
+/// Pivots are chosen randomly, long ranges are partitioned around a pair of pivots chosen from
+/// a random sample by Floyd-Rivest algorithm, which requires about n + min(k, n - k) comparisons
+/// on average. When several statistics are requested, each partition is done around the middle
+/// one of them, so that the rest are split into halves. Each time a part keeping more than 7/8
+/// of elements gets all the statistics the split is counted as bad, after 3 bad splits in a row
+/// pivots are chosen by median of medians, which guarantees that each partition shrinks the
+/// range by at least 3/10. Between two good splits only a constant number of linear passes is
+/// made, and each good split shrinks the range by a constant factor, so the total time is linear
+/// for any seed. Elements equal to the pivot are grouped together, so inputs with many repeated
+/// values are processed in linear time too.
 ///
-/// To avoid quadratic time in the worst case, we analyze sizes of two halves which were
-/// produced by the algorithm on each step and if one of the halves is much bigger than another
-/// one -- give up and use sort
+/// The same seed gives the same pivots, so that both the result (which elements equal to each
+/// other end up at the positions ks) and the running time are reproducible. If seed is None,
+/// the global seed set by set_seed is used
 crate fn kth_stats_recur<T: Copy + PartialOrd + Send + Debug>(
     xs: &mut [T],
     ks: &mut [usize],
+    seed: Option<u64>,
 ) -> IntHashMap<usize, T> {
     let xs_len = xs.len();
     let ks_vec = &mut ks.to_vec();
@@ -476,18 +772,134 @@ crate fn kth_stats_recur<T: Copy + PartialOrd + Send + Debug>(
     ks_vec.sort_unstable();
     ks_vec.dedup();
 
-    let mut rand_range = init_rand();
+    let mut rand_range = init_rand(seed);
 
-    kth_stat_helper(&mut rand_range, xs, ks_vec, 0, xs_len, false)
+    kth_stat_helper(&mut rand_range, xs, ks_vec, 0, xs_len, KTH_BAD_SPLITS)
+}
+
+/// Same as kth_stats_recur, but long inputs are processed by radix selection, which is faster
+/// and takes linear time no matter how the data looks like. The seed is used only by comparison
+/// based selection of short inputs
+crate fn radix_kth_stats_recur<T: Copy + PartialOrd + Send + Debug + RadixKey>(
+    xs: &mut [T],
+    ks: &mut [usize],
+    seed: Option<u64>,
+) -> IntHashMap<usize, T> {
+    if xs.len() >= RADIX_SELECT_THRESHOLD {
+        radix_select_stats(xs, ks)
+    } else {
+        kth_stats_recur(xs, ks, seed)
+    }
+}
+
+/// Check that all ks are within bounds of xs and find them by select, results are returned in
+/// the same order as requested indices
+fn kth_stats_by<T: Copy>(
+    xs: &mut [T],
+    ks: &mut [usize],
+    seed: Option<u64>,
+    select: fn(&mut [T], &mut [usize], Option<u64>) -> IntHashMap<usize, T>,
+) -> Result<Vec<T>, MyError> {
+    let len = xs.len();
+
+    if let Some(&k) = ks.iter().find(|&&k| k >= len) {
+        return Err(MyError::KthOutOfRange { k, len });
+    }
+
+    let found = select(xs, ks, seed);
+
+    Ok(ks.iter().map(|k| found[k]).collect())
 }
 
 crate fn kth_stat<T: Copy + PartialOrd + Send + Debug>(
     xs: &mut [T],
     k: usize,
+    seed: Option<u64>,
 ) -> Result<T, MyError> {
-    Ok(kth_stats_recur(xs, &mut [k])[&k])
+    kth_stats_by(xs, &mut [k], seed, kth_stats_recur).map(|found| found[0])
+}
+
+/// Find several order statistics at once, all of them are found during a single pass of
+/// kth_stats_recur, results are returned in the same order as requested indices
+crate fn kth_stats<T: Copy + PartialOrd + Send + Debug>(
+    xs: &mut [T],
+    mut ks: Vec<usize>,
+    seed: Option<u64>,
+) -> Result<Vec<T>, MyError> {
+    kth_stats_by(xs, &mut ks, seed, kth_stats_recur)
+}
+
+/// Same as kth_stat, radix selection is used for long inputs
+crate fn radix_kth_stat<T: Copy + PartialOrd + Send + Debug + RadixKey>(
+    xs: &mut [T],
+    k: usize,
+    seed: Option<u64>,
+) -> Result<T, MyError> {
+    kth_stats_by(xs, &mut [k], seed, radix_kth_stats_recur).map(|found| found[0])
+}
+
+/// Same as kth_stats, radix selection is used for long inputs
+crate fn radix_kth_stats<T: Copy + PartialOrd + Send + Debug + RadixKey>(
+    xs: &mut [T],
+    mut ks: Vec<usize>,
+    seed: Option<u64>,
+) -> Result<Vec<T>, MyError> {
+    kth_stats_by(xs, &mut ks, seed, radix_kth_stats_recur)
 }
 
+/// Move the element of rank k to position k of xs, so that no element before it is bigger and
+/// no element after it is smaller. Elements are compared by a fallible function, which is
+/// needed for values compared by calling into python, the first error stops the selection and
+/// is returned.
+///
+/// It is a quickselect with random pivots chosen the same way as by kth_stat, elements equal
+/// to the pivot are grouped together, so that repeated values don't slow down the search
+crate fn try_select_by<T, E, F>(
+    xs: &mut [T],
+    k: usize,
+    seed: Option<u64>,
+    mut compare: F,
+) -> Result<(), E>
+where
+    F: FnMut(&T, &T) -> Result<Ordering, E>,
+{
+    let mut rand_range = init_rand(seed);
+    let (mut left, mut right) = (0, xs.len());
+
+    while right - left > 1 {
+        let pivot_idx = rand_range(left, right);
+        xs.swap(left, pivot_idx);
+
+        // elements of [left, lt) are smaller than the pivot, elements of [lt, i) are equal to
+        // it and elements of [gt, right) are bigger, the rest are not processed yet
+        let (mut lt, mut i, mut gt) = (left, left + 1, right);
+
+        while i < gt {
+            match compare(&xs[i], &xs[lt])? {
+                Ordering::Less => {
+                    xs.swap(lt, i);
+                    lt += 1;
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    gt -= 1;
+                    xs.swap(i, gt);
+                }
+                Ordering::Equal => i += 1,
+            }
+        }
+
+        if k < lt {
+            right = lt;
+        } else if k >= gt {
+            left = gt;
+        } else {
+            break;
+        }
+    }
+
+    Ok(())
+}
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,122 @@
+use super::errors::MyError;
+
+use num::Float;
+use std::borrow::Cow;
+use std::str::FromStr;
+
+/// Defines how float statistics treat NaN values of input data
+///
+/// Propagate - result is NaN if there is at least one NaN in the data
+/// Raise - error is returned if there is at least one NaN in the data
+/// Omit - NaN values are ignored
+#[derive(Clone, Copy, Debug, PartialEq)]
+crate enum NanPolicy {
+    Propagate,
+    Raise,
+    Omit,
+}
+
+impl FromStr for NanPolicy {
+    type Err = MyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "propagate" => Ok(NanPolicy::Propagate),
+            "raise" => Ok(NanPolicy::Raise),
+            "omit" => Ok(NanPolicy::Omit),
+            _ => Err(MyError::UnknownNanPolicy {
+                policy: s.to_string(),
+            }),
+        }
+    }
+}
+
+impl NanPolicy {
+    /// Prepare data for computation according to the policy, returns the data to be used and a
+    /// flag telling whether the result must be replaced by NaN.
+    ///
+    /// To propagate NaN, all NaNs are replaced by zeros, so that the statistic may still be
+    /// computed by NanOutput::nan_or_else when the shape of its result depends on the data,
+    /// algorithms which rely on ordering of values don't break on such data
+    crate fn apply<T: Float>(self, xs: &[T]) -> Result<(Cow<'_, [T]>, bool), MyError> {
+        if !xs.iter().any(|x| x.is_nan()) {
+            return Ok((Cow::Borrowed(xs), false));
+        }
+
+        let mut ys = xs.to_vec();
+        let propagate = self.apply_mut(&mut ys)?;
+
+        Ok((Cow::Owned(ys), propagate))
+    }
+
+    /// Prepare data for counting of distinct values, NaN is counted as one more value, so it is
+    /// kept in the data if the policy is Propagate
+    crate fn apply_counting<T: Float>(self, xs: &[T]) -> Result<Cow<'_, [T]>, MyError> {
+        match self {
+            NanPolicy::Propagate => Ok(Cow::Borrowed(xs)),
+            _ => self.apply(xs).map(|(ys, _)| ys),
+        }
+    }
+
+    /// Same as apply, but modifies data in-place
+    crate fn apply_mut<T: Float>(self, xs: &mut Vec<T>) -> Result<bool, MyError> {
+        if !xs.iter().any(|x| x.is_nan()) {
+            return Ok(false);
+        }
+
+        match self {
+            NanPolicy::Raise => Err(MyError::NanInData),
+            NanPolicy::Omit => {
+                xs.retain(|x| !x.is_nan());
+                Ok(false)
+            }
+            NanPolicy::Propagate => {
+                for x in xs.iter_mut().filter(|x| x.is_nan()) {
+                    *x = T::zero();
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Result of a float statistic which may be replaced by NaN
+crate trait NanOutput: Sized {
+    /// Result of f, or NaN if propagate is set, i.e. the data contains NaN and the policy is
+    /// Propagate. Scalar results are NaN right away and f is not run at all, so that errors
+    /// depending on the data, such as absence of a unique mode, are not returned. Length of
+    /// vector results depends on the data, so f is run on data with NaNs replaced by zeros and
+    /// all the elements of its result are replaced by NaN
+    fn nan_or_else<F>(propagate: bool, f: F) -> Result<Self, MyError>
+    where
+        F: FnOnce() -> Result<Self, MyError>;
+}
+
+impl NanOutput for f64 {
+    #[inline]
+    fn nan_or_else<F>(propagate: bool, f: F) -> Result<Self, MyError>
+    where
+        F: FnOnce() -> Result<Self, MyError>,
+    {
+        if propagate {
+            Ok(std::f64::NAN)
+        } else {
+            f()
+        }
+    }
+}
+
+impl NanOutput for Vec<f64> {
+    fn nan_or_else<F>(propagate: bool, f: F) -> Result<Self, MyError>
+    where
+        F: FnOnce() -> Result<Self, MyError>,
+    {
+        let xs = f()?;
+
+        if propagate {
+            Ok(vec![std::f64::NAN; xs.len()])
+        } else {
+            Ok(xs)
+        }
+    }
+}
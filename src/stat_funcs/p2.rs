@@ -0,0 +1,184 @@
+use super::errors::MyError;
+
+/// P-square algorithm by Jain and Chlamtac for estimating quantiles without storing values, see:
+///
+/// https://www.cse.wustl.edu/~jain/papers/ftp/psqr.pdf
+///
+/// Each quantile p is tracked by a marker, extra markers are placed at minimum, maximum and in the
+/// middle between adjacent quantiles, so that estimation of m quantiles requires 2m + 3 markers.
+/// Heights of markers are adjusted by piecewise-parabolic interpolation as values arrive
+#[derive(Clone, Debug)]
+crate struct P2Quantiles {
+    // requested quantiles and indices of their markers
+    ps: Vec<f64>,
+    markers: Vec<usize>,
+    // quantile of the distribution tracked by each marker
+    increments: Vec<f64>,
+    heights: Vec<f64>,
+    positions: Vec<f64>,
+    desired: Vec<f64>,
+    count: u64,
+}
+
+impl P2Quantiles {
+    crate fn new(ps: &[f64]) -> Result<Self, MyError> {
+        if ps.is_empty() {
+            return Err(MyError::NoQuantilesGiven);
+        } else if ps.iter().any(|p| !(*p > 0.0 && *p < 1.0)) {
+            return Err(MyError::InvalidQuantile);
+        }
+
+        let mut sorted = ps.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted.dedup();
+
+        let mut increments = vec![0.0];
+        for p in &sorted {
+            let prev = increments[increments.len() - 1];
+            increments.push((prev + p) / 2.0);
+            increments.push(*p);
+        }
+        increments.push((sorted[sorted.len() - 1] + 1.0) / 2.0);
+        increments.push(1.0);
+
+        let markers = ps
+            .iter()
+            .map(|p| 2 * sorted.iter().position(|x| x == p).unwrap() + 2)
+            .collect();
+
+        let n = increments.len();
+
+        Ok(P2Quantiles {
+            ps: ps.to_vec(),
+            markers,
+            desired: increments.iter().map(|dn| 1.0 + (n - 1) as f64 * dn).collect(),
+            increments,
+            heights: Vec::with_capacity(n),
+            positions: (1..=n).map(|i| i as f64).collect(),
+            count: 0,
+        })
+    }
+
+    crate fn push(&mut self, x: f64) -> Result<(), MyError> {
+        if x.is_nan() {
+            return Err(MyError::NanInData);
+        }
+
+        self.count += 1;
+        let n = self.increments.len();
+
+        // the first n values are kept as heights of markers
+        if self.heights.len() < n {
+            self.heights.push(x);
+            if self.heights.len() == n {
+                self.heights.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            }
+            return Ok(());
+        }
+
+        // index of the cell between markers k and k + 1 containing x, extremes are updated
+        let k = if x < self.heights[0] {
+            self.heights[0] = x;
+            0
+        } else if x >= self.heights[n - 1] {
+            self.heights[n - 1] = x;
+            n - 2
+        } else {
+            self.heights.iter().rposition(|h| *h <= x).unwrap()
+        };
+
+        for pos in &mut self.positions[k + 1..] {
+            *pos += 1.0;
+        }
+        for (desired, dn) in self.desired.iter_mut().zip(&self.increments) {
+            *desired += dn;
+        }
+
+        for i in 1..n - 1 {
+            let d = self.desired[i] - self.positions[i];
+
+            if (d >= 1.0 && self.positions[i + 1] - self.positions[i] > 1.0)
+                || (d <= -1.0 && self.positions[i - 1] - self.positions[i] < -1.0)
+            {
+                let d = d.signum();
+                let height = self.parabolic(i, d);
+
+                self.heights[i] = if self.heights[i - 1] < height && height < self.heights[i + 1] {
+                    height
+                } else {
+                    self.linear(i, d)
+                };
+                self.positions[i] += d;
+            }
+        }
+
+        Ok(())
+    }
+
+    crate fn extend(&mut self, xs: &[f64]) -> Result<(), MyError> {
+        for x in xs {
+            self.push(*x)?;
+        }
+        Ok(())
+    }
+
+    /// Piecewise-parabolic prediction of the height of marker i moved by d
+    fn parabolic(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+
+        q[i] + d / (n[i + 1] - n[i - 1])
+            * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]))
+    }
+
+    /// Linear prediction of the height of marker i moved by d towards its neighbour
+    fn linear(&self, i: usize, d: f64) -> f64 {
+        let (q, n) = (&self.heights, &self.positions);
+        let j = if d > 0.0 { i + 1 } else { i - 1 };
+
+        q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+    }
+
+    /// Current estimates in the order of requested quantiles. While there are fewer values than
+    /// markers, quantiles are computed exactly by linear interpolation between sorted values
+    crate fn quantiles(&self) -> Result<Vec<f64>, MyError> {
+        if self.count == 0 {
+            return Err(MyError::NoEnoughDataForQuantiles);
+        }
+
+        if self.heights.len() < self.increments.len() {
+            let mut xs = self.heights.clone();
+            xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            let estimates = self.ps.iter().map(|p| {
+                let rank = p * (xs.len() - 1) as f64;
+                let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+                xs[lo] + (xs[hi] - xs[lo]) * (rank - lo as f64)
+            });
+
+            return Ok(estimates.collect());
+        }
+
+        Ok(self.markers.iter().map(|i| self.heights[*i]).collect())
+    }
+
+    crate fn count(&self) -> u64 {
+        self.count
+    }
+
+    crate fn min(&self) -> Result<f64, MyError> {
+        self.heights
+            .iter()
+            .cloned()
+            .fold(None, |acc: Option<f64>, x| Some(acc.map_or(x, |y| y.min(x))))
+            .ok_or(MyError::NoEnoughDataForMinMax)
+    }
+
+    crate fn max(&self) -> Result<f64, MyError> {
+        self.heights
+            .iter()
+            .cloned()
+            .fold(None, |acc: Option<f64>, x| Some(acc.map_or(x, |y| y.max(x))))
+            .ok_or(MyError::NoEnoughDataForMinMax)
+    }
+}
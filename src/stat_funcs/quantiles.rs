@@ -0,0 +1,212 @@
+use super::errors::MyError;
+use super::kth_stats_recur;
+
+use num::Float;
+use std::fmt::Debug;
+use std::str::FromStr;
+
+/// Method used to interpolate between order statistics when computing quantiles.
+///
+/// Exclusive and Inclusive behave exactly as the corresponding methods of python's
+/// statistics.quantiles, HyndmanFan(1..=9) are the sample quantile definitions from
+/// Hyndman, R. J. and Fan, Y. (1996) "Sample quantiles in statistical packages", these are the
+/// same as `type` argument of R's quantile function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+crate enum QuantileMethod {
+    Exclusive,
+    Inclusive,
+    HyndmanFan(u8),
+}
+
+impl FromStr for QuantileMethod {
+    type Err = MyError;
+
+    /// Accepts "exclusive", "inclusive" and "type1" .. "type9"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "exclusive" => Ok(QuantileMethod::Exclusive),
+            "inclusive" => Ok(QuantileMethod::Inclusive),
+            _ => {
+                // the prefix is removed exactly once and the rest must consist of digits only,
+                // because parse accepts a leading sign
+                let number = if s.starts_with("type") { &s[4..] } else { "" };
+                let digits = !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit());
+
+                match number.parse::<u8>() {
+                    Ok(t) if digits && t >= 1 && t <= 9 => Ok(QuantileMethod::HyndmanFan(t)),
+                    _ => Err(MyError::UnknownQuantileMethod {
+                        method: s.to_string(),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// Describes a single cut point as a weighted sum of two order statistics:
+///
+/// q = (xs[lo] * (den - delta) + xs[hi] * delta) / den
+///
+/// indices are 0-based, delta may be negative or bigger than den for python's exclusive method
+/// which extrapolates beyond the data range
+#[derive(Debug)]
+struct CutPoint {
+    lo: usize,
+    hi: usize,
+    delta: i64,
+    den: i64,
+}
+
+impl CutPoint {
+    #[inline]
+    fn single(idx: usize) -> Self {
+        CutPoint {
+            lo: idx,
+            hi: idx,
+            delta: 0,
+            den: 1,
+        }
+    }
+}
+
+/// Translate 1-based (possibly out of range) position into 0-based index clamped to data bounds
+#[inline]
+fn clamp_pos(pos: i64, len: usize) -> usize {
+    (pos.max(1).min(len as i64) - 1) as usize
+}
+
+/// Computes position of i-th cut point out of n for the given method and data length
+fn cut_point(method: QuantileMethod, i: usize, n: usize, len: usize) -> CutPoint {
+    let (i, n, ld) = (i as i64, n as i64, len as i64);
+
+    match method {
+        QuantileMethod::Exclusive => {
+            // straight port of python's statistics.quantiles
+            let m = ld + 1;
+            let j = (i * m / n).max(1).min(ld - 1);
+            CutPoint {
+                lo: (j - 1) as usize,
+                hi: j as usize,
+                delta: i * m - j * n,
+                den: n,
+            }
+        }
+
+        QuantileMethod::Inclusive => {
+            let m = ld - 1;
+            let j = i * m / n;
+            CutPoint {
+                lo: j as usize,
+                hi: (j + 1) as usize,
+                delta: i * m - j * n,
+                den: n,
+            }
+        }
+
+        // discontinuous sample quantiles, position is h = ld * p (h = ld * p - 1/2 for the
+        // third type), where p = i / n, all computations are performed on integers to avoid
+        // rounding issues while checking whether h has fractional part
+        QuantileMethod::HyndmanFan(t) if t <= 3 => {
+            let (num, den) = if t == 3 {
+                (2 * ld * i - n, 2 * n)
+            } else {
+                (ld * i, n)
+            };
+
+            let j = num.div_euclid(den);
+            let g = num.rem_euclid(den);
+
+            match t {
+                1 if g == 0 => CutPoint::single(clamp_pos(j, len)),
+                2 if g == 0 => CutPoint {
+                    lo: clamp_pos(j, len),
+                    hi: clamp_pos(j + 1, len),
+                    delta: 1,
+                    den: 2,
+                },
+                3 if g == 0 && j % 2 == 0 => CutPoint::single(clamp_pos(j, len)),
+                _ => CutPoint::single(clamp_pos(j + 1, len)),
+            }
+        }
+
+        // continuous sample quantiles, position is h = ld * p + m, for each type m depends on p,
+        // it is represented here as a fraction (a * i + b * n) / (d * n)
+        QuantileMethod::HyndmanFan(t) => {
+            let (a, b, d) = match t {
+                4 => (ld, 0, 1),
+                5 => (2 * ld, 1, 2),
+                6 => (ld + 1, 0, 1),
+                7 => (ld - 1, 1, 1),
+                8 => (3 * ld + 1, 1, 3),
+                _ => (8 * ld + 2, 3, 8),
+            };
+
+            let num = a * i + b * n;
+            let den = d * n;
+
+            let j = num / den;
+
+            if j < 1 {
+                CutPoint::single(0)
+            } else if j >= ld {
+                CutPoint::single(len - 1)
+            } else if num % den == 0 {
+                CutPoint::single((j - 1) as usize)
+            } else {
+                CutPoint {
+                    lo: (j - 1) as usize,
+                    hi: j as usize,
+                    delta: num - j * den,
+                    den,
+                }
+            }
+        }
+    }
+}
+
+/// Divide data into n continuous intervals with equal probability, returns a list of n - 1 cut
+/// points separating the intervals.
+///
+/// All the order statistics required are found by a single kth_stats_recur call
+crate fn quantiles<T>(xs: &mut [T], n: usize, method: QuantileMethod) -> Result<Vec<T>, MyError>
+where
+    T: Float + Send + Debug,
+{
+    if n < 1 {
+        return Err(MyError::QuantilesInvalidN);
+    }
+
+    let len = xs.len();
+
+    if len == 0 {
+        return Err(MyError::NoEnoughDataForQuantiles);
+    } else if len == 1 {
+        return Ok(vec![xs[0]; n - 1]);
+    }
+
+    let cuts = (1..n)
+        .map(|i| cut_point(method, i, n, len))
+        .collect::<Vec<CutPoint>>();
+
+    let mut ks = cuts
+        .iter()
+        .flat_map(|c| vec![c.lo, c.hi])
+        .collect::<Vec<usize>>();
+
+    let found = kth_stats_recur(xs, &mut ks, None);
+
+    Ok(cuts
+        .iter()
+        .map(|c| {
+            let (lo, hi) = (found[&c.lo], found[&c.hi]);
+
+            if c.lo == c.hi {
+                lo
+            } else {
+                let den = T::from(c.den).unwrap();
+                let delta = T::from(c.delta).unwrap();
+                (lo * (den - delta) + hi * delta) / den
+            }
+        })
+        .collect())
+}
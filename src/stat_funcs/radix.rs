@@ -0,0 +1,251 @@
+use super::PAR_STATS_CHUNK;
+
+use int_hash::IntHashMap;
+use ordered_float::OrderedFloat;
+use rayon::prelude::*;
+use std::cmp::{max, min};
+
+// maximum number of key bits examined by each pass of radix selection, passes over shorter
+// inputs examine fewer bits, so that there are about 8 elements per bucket
+const RADIX_BITS: u32 = 16;
+
+// buckets smaller than this are sorted instead of being split further
+const RADIX_SORT_THRESHOLD: usize = 1 << 10;
+
+/// Value which can be mapped into an unsigned integer key of the same order, so that values may
+/// be selected by bits of their keys instead of comparisons
+crate trait RadixKey: Copy + Send + Sync {
+    fn radix_key(self) -> u64;
+}
+
+impl RadixKey for u64 {
+    #[inline]
+    fn radix_key(self) -> u64 {
+        self
+    }
+}
+
+impl RadixKey for i64 {
+    // flipping the sign bit moves negative numbers below positive ones
+    #[inline]
+    fn radix_key(self) -> u64 {
+        self as u64 ^ (1 << 63)
+    }
+}
+
+impl RadixKey for f64 {
+    // bits of positive floats are ordered as the floats themselves and bits of negative ones are
+    // ordered in reverse, so all bits of negative floats are flipped and only the sign bit of
+    // positive ones, the mask is computed without branching on the sign. NaNs are greater than
+    // any other value, the same as in OrderedFloat
+    #[inline]
+    fn radix_key(self) -> u64 {
+        if self.is_nan() {
+            return u64::max_value();
+        }
+
+        let bits = self.to_bits();
+        let mask = ((bits as i64) >> 63) as u64 | (1 << 63);
+
+        bits ^ mask
+    }
+}
+
+impl RadixKey for OrderedFloat<f64> {
+    #[inline]
+    fn radix_key(self) -> u64 {
+        self.into_inner().radix_key()
+    }
+}
+
+/// Bucket of a key not less than base, keys are counted from the base, so that ranges of keys
+/// containing a power of two, such as keys of small negative and positive ints, are split evenly
+#[inline]
+fn digit(key: u64, base: u64, shift: u32) -> usize {
+    ((key - base) >> shift) as usize
+}
+
+/// Elements of a bucket along with the smallest and the biggest of their keys
+struct Bucket<T> {
+    xs: Vec<T>,
+    min_key: u64,
+    max_key: u64,
+}
+
+impl<T: RadixKey> Bucket<T> {
+    fn with_capacity(capacity: usize) -> Self {
+        Bucket {
+            xs: Vec::with_capacity(capacity),
+            min_key: u64::max_value(),
+            max_key: 0,
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, x: T, key: u64) {
+        self.min_key = min(self.min_key, key);
+        self.max_key = max(self.max_key, key);
+        self.xs.push(x);
+    }
+}
+
+/// Smallest and biggest keys of elements, chunks of the input are processed in parallel
+fn key_range<T: RadixKey>(xs: &[T]) -> (u64, u64) {
+    xs.par_chunks(PAR_STATS_CHUNK)
+        .map(|chunk| {
+            chunk.iter().fold((u64::max_value(), 0), |(lo, hi), x| {
+                let key = x.radix_key();
+                (min(lo, key), max(hi, key))
+            })
+        })
+        .reduce(
+            || (u64::max_value(), 0),
+            |a, b| (min(a.0, b.0), max(a.1, b.1)),
+        )
+}
+
+/// Count of elements in each bucket, chunks of the input are counted in parallel
+fn histogram<T: RadixKey>(xs: &[T], base: u64, shift: u32, n: usize) -> Vec<usize> {
+    xs.par_chunks(PAR_STATS_CHUNK)
+        .map(|chunk| {
+            let mut counts = vec![0; n];
+            for x in chunk {
+                counts[digit(x.radix_key(), base, shift)] += 1;
+            }
+            counts
+        })
+        .reduce(
+            || vec![0; n],
+            |mut a, b| {
+                for (x, y) in a.iter_mut().zip(b) {
+                    *x += y;
+                }
+                a
+            },
+        )
+}
+
+/// Copy elements of the buckets which have slots, each rayon job collects elements of its own
+/// chunk and then parts of the same bucket are concatenated into a bucket of the known size
+fn scatter<T: RadixKey>(
+    xs: &[T],
+    base: u64,
+    shift: u32,
+    slots: &[Option<usize>],
+    sizes: &[usize],
+) -> Vec<Bucket<T>> {
+    let chunks = xs
+        .par_chunks(PAR_STATS_CHUNK)
+        .map(|chunk| {
+            let mut buckets = sizes
+                .iter()
+                .map(|_| Bucket::with_capacity(0))
+                .collect::<Vec<_>>();
+            for x in chunk {
+                let key = x.radix_key();
+                if let Some(i) = slots[digit(key, base, shift)] {
+                    buckets[i].push(*x, key);
+                }
+            }
+            buckets
+        })
+        .collect::<Vec<_>>();
+
+    let mut buckets = sizes
+        .iter()
+        .map(|size| Bucket::with_capacity(*size))
+        .collect::<Vec<_>>();
+
+    for chunk in chunks {
+        for (bucket, part) in buckets.iter_mut().zip(chunk) {
+            bucket.min_key = min(bucket.min_key, part.min_key);
+            bucket.max_key = max(bucket.max_key, part.max_key);
+            bucket.xs.extend(part.xs);
+        }
+    }
+
+    buckets
+}
+
+/// Find order statistics ks (sorted) of xs, offset is the number of elements smaller than any
+/// element of xs. Keys of all the elements are between min_key and max_key, so only the lowest
+/// bits needed to count from min_key to max_key are examined
+fn radix_select<T: RadixKey>(
+    xs: &[T],
+    ks: &[usize],
+    offset: usize,
+    (min_key, max_key): (u64, u64),
+    found: &mut IntHashMap<usize, T>,
+) {
+    if min_key == max_key {
+        // all the keys are equal
+        for k in ks {
+            found.insert(*k, xs[0]);
+        }
+        return;
+    }
+
+    if xs.len() <= RADIX_SORT_THRESHOLD {
+        let mut ys = xs.to_vec();
+        ys.sort_unstable_by_key(|x| x.radix_key());
+
+        for k in ks {
+            found.insert(*k, ys[*k - offset]);
+        }
+        return;
+    }
+
+    let bits = 64 - (max_key - min_key).leading_zeros();
+    let width = min(RADIX_BITS, 61 - (xs.len() as u64).leading_zeros());
+    let shift = bits.saturating_sub(width);
+    let counts = histogram(xs, min_key, shift, digit(max_key, min_key, shift) + 1);
+
+    // buckets which contain some of ks, along with their offsets and statistics
+    let mut buckets = Vec::new();
+    let (mut start, mut i) = (offset, 0);
+
+    for (bucket, count) in counts.iter().enumerate() {
+        let j = i + ks[i..].iter().take_while(|k| **k < start + count).count();
+
+        if j > i {
+            buckets.push((bucket, start, &ks[i..j]));
+        }
+
+        start += count;
+        i = j;
+    }
+
+    let mut slots = vec![None; counts.len()];
+    for (slot, (bucket, _, _)) in buckets.iter().enumerate() {
+        slots[*bucket] = Some(slot);
+    }
+
+    let sizes = buckets.iter().map(|(bucket, _, _)| counts[*bucket]).collect::<Vec<_>>();
+    let parts = scatter(xs, min_key, shift, &slots, &sizes);
+
+    for ((_, start, ks), part) in buckets.iter().zip(parts) {
+        radix_select(&part.xs, ks, *start, (part.min_key, part.max_key), found);
+    }
+}
+
+/// Order statistics found by MSD radix selection: elements are split into buckets by the highest
+/// bits of differences between their keys and the smallest key, and only buckets containing some
+/// of ks are split further by the next bits. Each pass examines at least 8 bits and takes linear
+/// time, so there are at most 8 passes no matter how the data looks like, histograms and buckets
+/// are filled in parallel. The input is not reordered.
+///
+/// Keys of equal floats 0.0 and -0.0 differ, -0.0 is considered to be the smaller one
+crate fn radix_select_stats<T: RadixKey>(xs: &[T], ks: &[usize]) -> IntHashMap<usize, T> {
+    let mut ks = ks.to_vec();
+
+    ks.sort_unstable();
+    ks.dedup();
+
+    let mut found = IntHashMap::default();
+
+    if !ks.is_empty() {
+        radix_select(xs, &ks, 0, key_range(xs), &mut found);
+    }
+
+    found
+}
@@ -0,0 +1,204 @@
+use super::errors::MyError;
+use super::Accumulator;
+
+use num::{Float, FromPrimitive};
+use std::fmt::Debug;
+use std::ops::Add;
+
+/// Streaming accumulator of mean and variance.
+///
+/// Naive implementations of variance/mean computation suffer from a lack of precision
+/// therefor more advanced and much more accurate technique will be used, see:
+///
+/// https://math.stackexchange.com/questions/20593/calculate-variance-from-a-stream-of-sample-values
+/// https://www.johndcook.com/blog/standard_deviation/
+///
+/// for details
+#[derive(Clone, Debug)]
+crate struct RunningStats<T> {
+    count: usize,
+    mean: T,
+    // sum of squared differences from the current mean
+    m2: T,
+    min: T,
+    max: T,
+}
+
+impl<T> RunningStats<T>
+where
+    T: Float + FromPrimitive + Debug,
+{
+    crate fn new() -> Self {
+        RunningStats {
+            count: 0,
+            mean: T::zero(),
+            m2: T::zero(),
+            min: T::infinity(),
+            max: T::neg_infinity(),
+        }
+    }
+
+    #[inline]
+    crate fn push(&mut self, x: T) {
+        self.count += 1;
+
+        if self.count == 1 {
+            self.mean = x;
+            self.m2 = T::zero();
+        } else {
+            let common_diff = x - self.mean;
+
+            self.mean = self.mean + common_diff / T::from_usize(self.count).unwrap();
+            self.m2 = self.m2 + common_diff * (x - self.mean);
+        }
+
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+    }
+
+    crate fn extend(&mut self, xs: &[T]) {
+        for x in xs {
+            self.push(*x);
+        }
+    }
+
+    /// Combine statistics of two disjoint data sets as if all their values were pushed into a
+    /// single accumulator, uses pairwise update by Chan et al., see:
+    ///
+    /// https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Parallel_algorithm
+    crate fn merge(&mut self, other: &RunningStats<T>) {
+        if other.count == 0 {
+            return;
+        } else if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+
+        let (na, nb) = (
+            T::from_usize(self.count).unwrap(),
+            T::from_usize(other.count).unwrap(),
+        );
+        let n = na + nb;
+        let delta = other.mean - self.mean;
+
+        self.count += other.count;
+        self.mean = self.mean + delta * (nb / n);
+        self.m2 = self.m2 + other.m2 + delta * delta * (na * nb / n);
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Raw accumulator state: (count, mean, sum of squared differences, min, max), it is
+    /// useful to pass partial results between processes
+    crate fn state(&self) -> (usize, T, T, T, T) {
+        (self.count, self.mean, self.m2, self.min, self.max)
+    }
+
+    crate fn from_state(state: (usize, T, T, T, T)) -> Self {
+        let (count, mean, m2, min, max) = state;
+        RunningStats {
+            count,
+            mean,
+            m2,
+            min,
+            max,
+        }
+    }
+
+    crate fn count(&self) -> usize {
+        self.count
+    }
+
+    crate fn mean(&self) -> Result<T, MyError> {
+        if self.count == 0 {
+            Err(MyError::NoEnoughDataForMean)
+        } else {
+            Ok(self.mean)
+        }
+    }
+
+    /// Sample variance of all the values pushed so far
+    crate fn variance(&self) -> Result<T, MyError> {
+        if self.count < 2 {
+            Err(MyError::NoEnoughDataForVariance)
+        } else {
+            Ok(self.m2 / T::from_usize(self.count - 1).unwrap())
+        }
+    }
+
+    /// Population variance of all the values pushed so far
+    crate fn pvariance(&self) -> Result<T, MyError> {
+        if self.count == 0 {
+            Err(MyError::NoEnoughDataForPopulationVariance)
+        } else {
+            Ok(self.m2 / T::from_usize(self.count).unwrap())
+        }
+    }
+
+    crate fn stdev(&self) -> Result<T, MyError> {
+        // variance can't be a negative value no additional checks needed
+        Ok(self.variance()?.sqrt())
+    }
+
+    crate fn pstdev(&self) -> Result<T, MyError> {
+        Ok(self.pvariance()?.sqrt())
+    }
+
+    crate fn min(&self) -> Result<T, MyError> {
+        if self.count == 0 {
+            Err(MyError::NoEnoughDataForMinMax)
+        } else {
+            Ok(self.min)
+        }
+    }
+
+    crate fn max(&self) -> Result<T, MyError> {
+        if self.count == 0 {
+            Err(MyError::NoEnoughDataForMinMax)
+        } else {
+            Ok(self.max)
+        }
+    }
+}
+
+impl<T> Default for RunningStats<T>
+where
+    T: Float + FromPrimitive + Debug,
+{
+    fn default() -> Self {
+        RunningStats::new()
+    }
+}
+
+impl<T> Accumulator<T> for RunningStats<T>
+where
+    T: Float + FromPrimitive + Debug,
+{
+    fn update(&mut self, xs: &[T]) -> Result<(), MyError> {
+        self.extend(xs);
+        Ok(())
+    }
+}
+
+impl<'a, T> From<&'a [T]> for RunningStats<T>
+where
+    T: Float + FromPrimitive + Debug,
+{
+    fn from(xs: &'a [T]) -> Self {
+        let mut stats = RunningStats::new();
+        stats.extend(xs);
+        stats
+    }
+}
+
+impl<T> Add for RunningStats<T>
+where
+    T: Float + FromPrimitive + Debug,
+{
+    type Output = RunningStats<T>;
+
+    fn add(mut self, other: RunningStats<T>) -> RunningStats<T> {
+        self.merge(&other);
+        self
+    }
+}
@@ -0,0 +1,164 @@
+use super::errors::MyError;
+use super::seeded_rng;
+
+use ordered_float::OrderedFloat;
+use rand::{Open01, Rng, XorShiftRng};
+use std::collections::{BinaryHeap, HashSet};
+
+/// Indices of k distinct elements of a population of size n chosen uniformly at random, in
+/// random order. Floyd's algorithm is used, so memory usage is O(k) regardless of n
+crate fn sample_indices(n: usize, k: usize, seed: Option<u64>) -> Result<Vec<usize>, MyError> {
+    if k > n {
+        return Err(MyError::SampleLargerThanPopulation);
+    }
+
+    let mut rng = seeded_rng(seed);
+    let mut chosen = HashSet::with_capacity(k);
+    let mut indices = Vec::with_capacity(k);
+
+    for j in n - k..n {
+        let t = rng.gen_range(0, j + 1);
+        let idx = if chosen.contains(&t) { j } else { t };
+
+        chosen.insert(idx);
+        indices.push(idx);
+    }
+
+    // Floyd's algorithm chooses a uniform subset, but not a uniform permutation of it
+    rng.shuffle(&mut indices);
+
+    Ok(indices)
+}
+
+/// Indices of k distinct elements chosen without replacement with probabilities proportional
+/// to their weights, in the order they would be drawn one by one. Elements with zero weights are
+/// never chosen.
+///
+/// Each element gets random key Exp(1) / weight and k elements having the smallest keys are
+/// chosen, see Efraimidis and Spirakis, "Weighted random sampling with a reservoir"
+crate fn weighted_sample_indices(
+    weights: &[f64],
+    k: usize,
+    seed: Option<u64>,
+) -> Result<Vec<usize>, MyError> {
+    if weights.iter().any(|w| !(*w >= 0.0) || w.is_infinite()) {
+        return Err(MyError::InvalidWeights);
+    } else if k > weights.iter().filter(|w| **w > 0.0).count() {
+        return Err(MyError::SampleLargerThanPopulation);
+    }
+
+    let mut rng = seeded_rng(seed);
+    // max-heap of k smallest keys seen so far
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+
+    for (idx, w) in weights.iter().enumerate().filter(|(_, w)| **w > 0.0) {
+        let Open01(u) = rng.gen::<Open01<f64>>();
+        heap.push((OrderedFloat(-u.ln() / w), idx));
+
+        if heap.len() > k {
+            heap.pop();
+        }
+    }
+
+    Ok(heap.into_sorted_vec().into_iter().map(|(_, idx)| idx).collect())
+}
+
+/// Uniform random sample of k items of a stream of unknown length, uses Algorithm L by Li, see:
+///
+/// https://dl.acm.org/doi/10.1145/198429.198435
+///
+/// Instead of drawing a random number for each item, the algorithm computes how many items to
+/// skip before the next replacement, so random numbers are drawn O(k log(n / k)) times
+#[derive(Clone, Debug)]
+crate struct Reservoir<T> {
+    k: usize,
+    items: Vec<T>,
+    count: u64,
+    // count of items at which the next replacement happens
+    next: u64,
+    w: f64,
+    rng: XorShiftRng,
+}
+
+impl<T> Reservoir<T> {
+    crate fn new(k: usize, seed: Option<u64>) -> Result<Self, MyError> {
+        if k == 0 {
+            return Err(MyError::ReservoirInvalidK);
+        }
+
+        Ok(Reservoir {
+            k,
+            items: Vec::with_capacity(k),
+            count: 0,
+            next: 0,
+            w: 1.0,
+            rng: seeded_rng(seed),
+        })
+    }
+
+    #[inline]
+    fn random(&mut self) -> f64 {
+        let Open01(u) = self.rng.gen::<Open01<f64>>();
+        u
+    }
+
+    /// Update w and schedule the next replacement
+    fn advance(&mut self) {
+        self.w *= (self.random().ln() / self.k as f64).exp();
+
+        // ln(1 - w) computed directly is zero for w below 1e-16, which makes skip infinite
+        let skip = (self.random().ln() / (-self.w).ln_1p()).floor();
+        // float to int conversion of negative, too large or non-finite numbers is undefined, w
+        // underflowing to zero gives infinite skip
+        let skip = if skip >= 0.0 && skip < 1e18 {
+            skip as u64
+        } else {
+            1 << 60
+        };
+
+        self.next = self.count.saturating_add(skip + 1);
+    }
+
+    crate fn push(&mut self, x: T) {
+        self.count += 1;
+
+        if self.items.len() < self.k {
+            self.items.push(x);
+
+            if self.items.len() == self.k {
+                self.advance();
+            }
+        } else if self.count == self.next {
+            let idx = self.rng.gen_range(0, self.k);
+            self.items[idx] = x;
+            self.advance();
+        }
+    }
+
+    crate fn extend(&mut self, xs: &[T])
+    where
+        T: Clone,
+    {
+        for x in xs {
+            // skipped items are not cloned
+            if self.items.len() < self.k || self.count + 1 == self.next {
+                self.push(x.clone());
+            } else {
+                self.count += 1;
+            }
+        }
+    }
+
+    /// Current sample, it contains min(k, count) items
+    crate fn sample(&self) -> &[T] {
+        &self.items
+    }
+
+    crate fn count(&self) -> u64 {
+        self.count
+    }
+
+    crate fn k(&self) -> usize {
+        self.k
+    }
+}
@@ -0,0 +1,102 @@
+use super::errors::MyError;
+
+/// Serializer of sketch state, numbers are stored as little-endian 64-bit words following 4-byte
+/// tag which identifies the type of a sketch and its format version
+crate struct ByteWriter {
+    buf: Vec<u8>,
+}
+
+impl ByteWriter {
+    crate fn new(tag: &[u8; 4]) -> Self {
+        ByteWriter { buf: tag.to_vec() }
+    }
+
+    crate fn put_u64(&mut self, x: u64) {
+        self.buf.extend_from_slice(&x.to_le_bytes());
+    }
+
+    crate fn put_f64(&mut self, x: f64) {
+        self.put_u64(x.to_bits());
+    }
+
+    crate fn put_bytes(&mut self, xs: &[u8]) {
+        self.buf.extend_from_slice(xs);
+    }
+
+    crate fn into_bytes(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Deserializer of data written by ByteWriter, any mismatch of the tag or data length results
+/// in InvalidSketchData error
+crate struct ByteReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ByteReader<'a> {
+    crate fn new(data: &'a [u8], tag: &[u8; 4]) -> Result<Self, MyError> {
+        if data.len() < tag.len() || &data[..tag.len()] != tag {
+            return Err(MyError::InvalidSketchData);
+        }
+
+        Ok(ByteReader {
+            data: &data[tag.len()..],
+        })
+    }
+
+    crate fn get_bytes(&mut self, n: usize) -> Result<&'a [u8], MyError> {
+        if self.data.len() < n {
+            return Err(MyError::InvalidSketchData);
+        }
+
+        let (bytes, rest) = self.data.split_at(n);
+        self.data = rest;
+
+        Ok(bytes)
+    }
+
+    crate fn get_u64(&mut self) -> Result<u64, MyError> {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(self.get_bytes(8)?);
+
+        Ok(u64::from_le_bytes(word))
+    }
+
+    crate fn get_f64(&mut self) -> Result<f64, MyError> {
+        self.get_u64().map(f64::from_bits)
+    }
+
+    /// Number of 64-bit words left unread
+    crate fn remaining(&self) -> usize {
+        self.data.len() / 8
+    }
+
+    /// Make sure all the data has been read
+    crate fn finish(self) -> Result<(), MyError> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(MyError::InvalidSketchData)
+        }
+    }
+}
+
+/// Value stored by a sketch which may be serialized along with it
+crate trait Serializable: Sized {
+    fn write_to(&self, writer: &mut ByteWriter);
+
+    fn read_from(reader: &mut ByteReader<'_>) -> Result<Self, MyError>;
+}
+
+impl Serializable for f64 {
+    #[inline]
+    fn write_to(&self, writer: &mut ByteWriter) {
+        writer.put_f64(*self);
+    }
+
+    #[inline]
+    fn read_from(reader: &mut ByteReader<'_>) -> Result<Self, MyError> {
+        reader.get_f64()
+    }
+}
@@ -0,0 +1,294 @@
+use super::errors::MyError;
+use super::serialize::{ByteReader, ByteWriter};
+
+use std::f64::consts::PI;
+
+const TAG: &[u8; 4] = b"TDG1";
+
+// incoming values are buffered and merged into centroids once the buffer holds this number of
+// values per unit of compression
+const BUFFER_FACTOR: f64 = 5.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: u64,
+}
+
+/// Merging t-digest by Dunning and Ertl, see:
+///
+/// https://arxiv.org/abs/1902.04023
+///
+/// Values are summarized by a sorted list of centroids, sizes of centroids are limited by k1
+/// scale function, so that centroids near the tails are small and quantiles close to 0 and 1
+/// are estimated more accurately. Number of centroids is about compression / 2
+#[derive(Clone, Debug)]
+crate struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<Centroid>,
+    count: u64,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    crate fn new(compression: f64) -> Result<Self, MyError> {
+        if !(compression >= 1.0) || compression.is_infinite() {
+            return Err(MyError::TDigestInvalidCompression);
+        }
+
+        Ok(TDigest {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::new(),
+            count: 0,
+            min: std::f64::INFINITY,
+            max: std::f64::NEG_INFINITY,
+        })
+    }
+
+    /// Infinite values can't be averaged into centroids, so only finite values are accepted
+    crate fn push(&mut self, x: f64) -> Result<(), MyError> {
+        if !x.is_finite() {
+            return Err(MyError::TDigestNonFiniteValue);
+        }
+
+        self.add_centroid(Centroid { mean: x, weight: 1 });
+        Ok(())
+    }
+
+    crate fn extend(&mut self, xs: &[f64]) -> Result<(), MyError> {
+        if xs.iter().any(|x| !x.is_finite()) {
+            return Err(MyError::TDigestNonFiniteValue);
+        }
+
+        for x in xs {
+            self.add_centroid(Centroid {
+                mean: *x,
+                weight: 1,
+            });
+        }
+        Ok(())
+    }
+
+    fn add_centroid(&mut self, c: Centroid) {
+        self.buffer.push(c);
+        self.count += c.weight;
+        self.min = self.min.min(c.mean);
+        self.max = self.max.max(c.mean);
+
+        if self.buffer.len() as f64 >= BUFFER_FACTOR * self.compression {
+            self.compress();
+        }
+    }
+
+    /// Combine digests built on disjoint data sets, centroids of the other digest are merged as
+    /// if they were values of the corresponding weight
+    crate fn merge(&mut self, other: &TDigest) {
+        for c in other.centroids.iter().chain(&other.buffer) {
+            self.add_centroid(*c);
+        }
+
+        // extreme values are usually merged into centroids
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+
+        self.compress();
+    }
+
+    /// k1 scale function, maps quantile to the index of a centroid
+    #[inline]
+    fn scale(&self, q: f64) -> f64 {
+        self.compression / (2.0 * PI) * (2.0 * q.min(1.0) - 1.0).asin()
+    }
+
+    /// Merge buffered values into centroids, adjacent centroids are merged while the merged
+    /// centroid spans no more than one unit of the scale function
+    fn compress(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let mut all = self.centroids.clone();
+        all.append(&mut self.buffer);
+        all.sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap());
+
+        let total = self.count as f64;
+        let mut result = Vec::with_capacity(self.compression as usize);
+
+        let mut cur = all[0];
+        let mut weight_before = 0;
+        let mut k_lo = self.scale(0.0);
+
+        for c in all.into_iter().skip(1) {
+            let q = (weight_before + cur.weight + c.weight) as f64 / total;
+
+            if self.scale(q) - k_lo <= 1.0 {
+                let weight = cur.weight + c.weight;
+                cur.mean += (c.mean - cur.mean) * c.weight as f64 / weight as f64;
+                cur.weight = weight;
+            } else {
+                weight_before += cur.weight;
+                k_lo = self.scale(weight_before as f64 / total);
+                result.push(cur);
+                cur = c;
+            }
+        }
+
+        result.push(cur);
+        self.centroids = result;
+    }
+
+    /// Points of piecewise linear approximation of the distribution as (value, rank) pairs:
+    /// minimum at rank 0, each centroid at the middle of its weight and maximum at rank count
+    fn knots(&mut self) -> Vec<(f64, f64)> {
+        self.compress();
+
+        let mut knots = Vec::with_capacity(self.centroids.len() + 2);
+        let mut weight_before = 0;
+
+        knots.push((self.min, 0.0));
+        for c in &self.centroids {
+            knots.push((c.mean, weight_before as f64 + c.weight as f64 / 2.0));
+            weight_before += c.weight;
+        }
+        knots.push((self.max, self.count as f64));
+
+        knots
+    }
+
+    /// Estimate value below which fraction q of data falls
+    crate fn quantile(&mut self, q: f64) -> Result<f64, MyError> {
+        if !(q >= 0.0 && q <= 1.0) {
+            return Err(MyError::InvalidQuantile);
+        } else if self.count == 0 {
+            return Err(MyError::NoEnoughDataForQuantiles);
+        }
+
+        let rank = q * self.count as f64;
+        let knots = self.knots();
+
+        let idx = knots[1..]
+            .iter()
+            .position(|x| x.1 >= rank)
+            .unwrap_or(knots.len() - 2);
+        let ((x0, r0), (x1, r1)) = (knots[idx], knots[idx + 1]);
+
+        if r1 <= r0 {
+            Ok(x1)
+        } else {
+            Ok(x0 + (x1 - x0) * (rank - r0) / (r1 - r0))
+        }
+    }
+
+    /// Estimate fraction of data which is less than or equal to x
+    crate fn cdf(&mut self, x: f64) -> Result<f64, MyError> {
+        if x.is_nan() {
+            return Err(MyError::NanInData);
+        } else if self.count == 0 {
+            return Err(MyError::NoEnoughDataForQuantiles);
+        }
+
+        if x < self.min {
+            return Ok(0.0);
+        } else if x >= self.max {
+            return Ok(1.0);
+        }
+
+        let knots = self.knots();
+
+        // the first knot having greater value than x, it always exists since x < max
+        let idx = knots.iter().position(|k| k.0 > x).unwrap();
+        let ((x0, r0), (x1, r1)) = (knots[idx - 1], knots[idx]);
+
+        let rank = r0 + (r1 - r0) * (x - x0) / (x1 - x0);
+        Ok(rank / self.count as f64)
+    }
+
+    crate fn count(&self) -> u64 {
+        self.count
+    }
+
+    crate fn min(&self) -> Result<f64, MyError> {
+        if self.count == 0 {
+            Err(MyError::NoEnoughDataForMinMax)
+        } else {
+            Ok(self.min)
+        }
+    }
+
+    crate fn max(&self) -> Result<f64, MyError> {
+        if self.count == 0 {
+            Err(MyError::NoEnoughDataForMinMax)
+        } else {
+            Ok(self.max)
+        }
+    }
+
+    crate fn compression(&self) -> f64 {
+        self.compression
+    }
+
+    crate fn to_bytes(&self) -> Vec<u8> {
+        let mut digest = self.clone();
+        digest.compress();
+
+        let mut writer = ByteWriter::new(TAG);
+
+        writer.put_f64(digest.compression);
+        writer.put_f64(digest.min);
+        writer.put_f64(digest.max);
+        writer.put_u64(digest.centroids.len() as u64);
+
+        for c in &digest.centroids {
+            writer.put_f64(c.mean);
+            writer.put_u64(c.weight);
+        }
+
+        writer.into_bytes()
+    }
+
+    crate fn from_bytes(data: &[u8]) -> Result<Self, MyError> {
+        let mut reader = ByteReader::new(data, TAG)?;
+
+        let compression = reader.get_f64()?;
+        let mut digest = TDigest::new(compression).map_err(|_| MyError::InvalidSketchData)?;
+
+        let (min, max) = (reader.get_f64()?, reader.get_f64()?);
+        let n = reader.get_u64()? as usize;
+
+        if n != reader.remaining() / 2 || n > 0 && !(min.is_finite() && max.is_finite()) {
+            return Err(MyError::InvalidSketchData);
+        }
+
+        for _ in 0..n {
+            let c = Centroid {
+                mean: reader.get_f64()?,
+                weight: reader.get_u64()?,
+            };
+
+            // centroids must be sorted and lie within [min, max]
+            let lo = digest.centroids.last().map_or(min, |prev| prev.mean);
+
+            if !(c.mean >= lo && c.mean <= max) || c.weight == 0 {
+                return Err(MyError::InvalidSketchData);
+            }
+
+            digest.centroids.push(c);
+            digest.count = digest
+                .count
+                .checked_add(c.weight)
+                .ok_or(MyError::InvalidSketchData)?;
+        }
+
+        reader.finish()?;
+
+        if n > 0 {
+            digest.min = min;
+            digest.max = max;
+        }
+
+        Ok(digest)
+    }
+}
@@ -1,6 +1,16 @@
-use crate::stat_funcs::{kth_stats_recur, mean, median_grouped, partition, pvariance, variance,
-harmonic_mean};
+use crate::stat_funcs::{count_distinct, global_seed, harmonic_mean, int_count_distinct, int_counts,
+                        int_mode, kll_rank_error, kth_stat, kth_stat_helper, kth_stats,
+                        kth_stats_recur, mean, median_grouped, mode, most_common, multimode,
+                        partition, pvariance, quantiles, radix_kth_stats, radix_select_stats,
+                        running_stats, sample_indices, set_seed, try_select_by, value_counts,
+                        variance, weighted_sample_indices, Accumulator, CountMinSketch, GKSummary,
+                        HarmonicMean, HeavyHitters, HyperLogLog, KllSketch, ModeCounter, NanOutput,
+                        NanPolicy, P2Quantiles, QuantileMethod, RadixKey, Reservoir, RunningStats,
+                        TDigest, PAR_STATS_CHUNK};
+use crate::stat_funcs::errors::MyError;
 use quickcheck::{quickcheck, TestResult};
+use std::collections::HashMap;
+use std::fmt::Debug;
 
 // round number up to $digits digits, convenient for some tests below
 macro_rules! round {
@@ -56,7 +66,7 @@ fn ensure_statistics(mut xs: Vec<u32>, mut ks: Vec<usize>) -> TestResult {
         let mut ys = xs.clone();
         let mut passed = true;
 
-        let result = kth_stats_recur(&mut ys, &mut ks);
+        let result = kth_stats_recur(&mut ys, &mut ks, None);
 
         xs.sort();
 
@@ -83,6 +93,231 @@ fn test_kth() {
     quickcheck(ensure_statistics as fn(Vec<u32>, Vec<usize>) -> TestResult);
 }
 
+#[test]
+fn test_kth_stats() {
+    let mut xs = [5, 1, 4, 2, 3];
+    assert_eq!(kth_stats(&mut xs, vec![4, 0, 2, 0], None).unwrap(), vec![5, 1, 3, 1]);
+
+    let mut xs = [5, 1, 4, 2, 3];
+    assert!(kth_stats(&mut xs, vec![1, 5], None).is_err());
+    assert!(kth_stat(&mut xs, 5, None).is_err());
+
+    let mut xs: [u32; 0] = [];
+    assert!(kth_stat(&mut xs, 0, None).is_err());
+    assert_eq!(kth_stats(&mut xs, vec![], None).unwrap(), vec![]);
+}
+
+#[test]
+fn test_kth_stats_seed() {
+    // zeros of different signs are equal, which one is selected depends on pivots
+    let xs = (0..10_000)
+        .map(|i| if i % 2 == 0 { 0.0 } else { -0.0 })
+        .collect::<Vec<f64>>();
+
+    let select = |seed| {
+        let mut ys = xs.clone();
+        let found = kth_stats(&mut ys, vec![0, 2500, 5000, 9999], seed).unwrap();
+        (found.iter().map(|x| x.to_bits()).collect::<Vec<u64>>(), ys)
+    };
+
+    for seed in 0..10 {
+        let (a, b) = (select(Some(seed)), select(Some(seed)));
+        assert_eq!(a.0, b.0);
+        assert!(a.1.iter().zip(&b.1).all(|(x, y)| x.to_bits() == y.to_bits()));
+    }
+
+    // the global seed is used when there is no seed in the call
+    set_seed(Some(42));
+    assert_eq!(global_seed(), Some(42));
+    assert_eq!(select(None).0, select(Some(42)).0);
+    set_seed(None);
+    assert_eq!(global_seed(), None);
+
+    set_seed(Some(std::u64::MAX));
+    assert_eq!(global_seed(), Some(std::u64::MAX));
+    set_seed(None);
+}
+
+#[test]
+fn test_kth_stats_adversarial() {
+    let n = 100_000;
+    let inputs: Vec<Vec<u32>> = vec![
+        (0..n).collect(),
+        (0..n).rev().collect(),
+        vec![7; n as usize],
+        (0..n).map(|i| i % 3).collect(),
+        // organ pipe
+        (0..n).map(|i| if i < n / 2 { i } else { n - i }).collect(),
+        (0..n).map(|i| (i * 7919) % 1013).collect(),
+    ];
+    let ks = vec![0, 1, 17, 5_000, 49_999, 50_000, 99_998, 99_999];
+
+    for xs in inputs {
+        let mut sorted = xs.clone();
+        sorted.sort();
+        let expected = ks.iter().map(|k| sorted[*k]).collect::<Vec<u32>>();
+
+        let mut ys = xs.clone();
+        assert_eq!(kth_stats(&mut ys, ks.clone(), Some(1)).unwrap(), expected);
+
+        // no bad splits are allowed, so that all pivots are medians of medians
+        let mut ys = xs.clone();
+        let mut rand_range = |_, _| unreachable!();
+        let found = kth_stat_helper(&mut rand_range, &mut ys, &ks, 0, n as usize, 0);
+        assert_eq!(ks.iter().map(|k| found[k]).collect::<Vec<u32>>(), expected);
+    }
+
+    let check = |mut xs: Vec<u32>, k: usize| -> TestResult {
+        if k >= xs.len() {
+            return TestResult::discard();
+        }
+        let mut sorted = xs.clone();
+        sorted.sort();
+        let mut rand_range = |_, _| unreachable!();
+        let found = kth_stat_helper(&mut rand_range, &mut xs, &[k], 0, sorted.len(), 0);
+        TestResult::from_bool(found[&k] == sorted[k] && xs[k] == sorted[k])
+    };
+    quickcheck(check as fn(Vec<u32>, usize) -> TestResult);
+}
+
+#[test]
+fn test_try_select_by() {
+    let check = |mut xs: Vec<u32>, k: usize, seed: u64| -> TestResult {
+        if k >= xs.len() {
+            return TestResult::discard();
+        }
+        let mut sorted = xs.clone();
+        sorted.sort();
+        let res = try_select_by(&mut xs, k, Some(seed), |a, b| Ok::<_, ()>(a.cmp(b)));
+        TestResult::from_bool(
+            res.is_ok()
+                && xs[k] == sorted[k]
+                && xs[..k].iter().all(|x| *x <= sorted[k])
+                && xs[k..].iter().all(|x| *x >= sorted[k]),
+        )
+    };
+    quickcheck(check as fn(Vec<u32>, usize, u64) -> TestResult);
+
+    // selection stops at the first error
+    let mut xs = (0..1000).rev().collect::<Vec<u32>>();
+    let mut calls = 0;
+    let res = try_select_by(&mut xs, 500, Some(1), |a: &u32, b: &u32| {
+        calls += 1;
+        if *a == 300 || *b == 300 {
+            Err(calls)
+        } else {
+            Ok(a.cmp(b))
+        }
+    });
+    assert_eq!(res, Err(calls));
+}
+
+#[test]
+fn test_kth_stats_many() {
+    // all percentiles of pseudo-random data with repeated values
+    let n = 200_003;
+    let xs = (0..n).map(|i| (i * 7919 + i / 13) % 50_021).collect::<Vec<usize>>();
+    let ks = (0..=100).map(|p| p * (n - 1) / 100).collect::<Vec<usize>>();
+
+    let mut sorted = xs.clone();
+    sorted.sort();
+
+    for seed in 0..5 {
+        let mut ys = xs.clone();
+        let found = kth_stats(&mut ys, ks.clone(), Some(seed)).unwrap();
+        assert_eq!(found, ks.iter().map(|k| sorted[*k]).collect::<Vec<usize>>());
+        // statistics are moved to their positions, as if the data was sorted
+        assert!(ks.iter().all(|k| ys[*k] == sorted[*k]));
+    }
+
+    // ranges long enough to be partitioned around sampled pivots
+    fn prop(xs: Vec<u16>, ks: Vec<usize>, copies: u8) -> TestResult {
+        if xs.is_empty() || ks.is_empty() {
+            return TestResult::discard();
+        }
+
+        let mut ys = (0..usize::from(copies) + 1)
+            .flat_map(|i| xs.iter().map(move |x| x.wrapping_mul(i as u16 + 1)))
+            .collect::<Vec<u16>>();
+        let ks = ks.iter().map(|k| k % ys.len()).collect::<Vec<usize>>();
+
+        let mut sorted = ys.clone();
+        sorted.sort();
+
+        let found = kth_stats(&mut ys, ks.clone(), None).unwrap();
+        TestResult::from_bool(found == ks.iter().map(|k| sorted[*k]).collect::<Vec<u16>>())
+    }
+
+    quickcheck(prop as fn(Vec<u16>, Vec<usize>, u8) -> TestResult);
+}
+
+#[test]
+fn test_radix_select() {
+    // keys have the same order as values
+    let floats = [
+        std::f64::NEG_INFINITY,
+        -1e300,
+        -1.5,
+        -std::f64::MIN_POSITIVE,
+        -0.0,
+        0.0,
+        std::f64::MIN_POSITIVE,
+        2.0,
+        std::f64::INFINITY,
+        std::f64::NAN,
+    ];
+    assert!(floats.windows(2).all(|w| w[0].radix_key() < w[1].radix_key()));
+    assert!((-std::f64::NAN).radix_key() == std::f64::NAN.radix_key());
+
+    let ints = [std::i64::MIN, -1_000_000, -1, 0, 1, std::i64::MAX];
+    assert!(ints.windows(2).all(|w| w[0].radix_key() < w[1].radix_key()));
+
+    // copies make inputs long enough to be split into buckets
+    fn check<T: RadixKey + PartialOrd + Debug>(xs: &[T], ks: &[usize], copies: usize) -> bool {
+        let ys = (0..copies).flat_map(|_| xs.iter().cloned()).collect::<Vec<T>>();
+        let ks = ks.iter().map(|k| k % ys.len()).collect::<Vec<usize>>();
+
+        let mut sorted = ys.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let found = radix_select_stats(&ys, &ks);
+        ks.iter().all(|k| found[k] == sorted[*k])
+    }
+
+    fn prop(xs: Vec<i64>, ys: Vec<u64>, zs: Vec<f64>, ks: Vec<usize>, copies: u8) -> TestResult {
+        if xs.is_empty() || ys.is_empty() || zs.is_empty() || zs.iter().any(|z| z.is_nan()) {
+            return TestResult::discard();
+        }
+
+        let copies = usize::from(copies) * 20 + 1;
+        let ys = ys.iter().map(|y| y.wrapping_mul(0x9e37_79b9_7f4a_7c15)).collect::<Vec<u64>>();
+
+        TestResult::from_bool(
+            check(&xs, &ks, copies) && check(&ys, &ks, copies) && check(&zs, &ks, copies),
+        )
+    }
+
+    quickcheck(prop as fn(Vec<i64>, Vec<u64>, Vec<f64>, Vec<usize>, u8) -> TestResult);
+
+    // long inputs are selected by radix selection, which doesn't reorder them
+    let n = 300_007;
+    let mut xs = (0..n)
+        .map(|i| ((i * 7919) % n) as f64 - n as f64 / 2.0)
+        .collect::<Vec<f64>>();
+    let ks = (0..=100).map(|p| p * (n - 1) / 100).collect::<Vec<usize>>();
+    let expected = ks.iter().map(|k| *k as f64 - n as f64 / 2.0).collect::<Vec<f64>>();
+
+    let copy = xs.clone();
+    assert_eq!(radix_kth_stats(&mut xs, ks.clone(), None).unwrap(), expected);
+    assert_eq!(xs, copy);
+    assert!(radix_kth_stats(&mut xs, vec![n], None).is_err());
+
+    let mut ys = vec![-3i64; n];
+    ys[17] = std::i64::MIN;
+    ys[n - 1] = 5;
+    assert_eq!(radix_kth_stats(&mut ys, vec![0, 1, n - 1], None).unwrap(), [std::i64::MIN, -3, 5]);
+}
+
 #[test]
 fn test_variance() {
     let input: Vec<f64> = vec![];
@@ -159,3 +394,830 @@ fn test_harmonic_mean() {
     let res = harmonic_mean(&xs);
     assert_eq!(round!(res.unwrap(), 4), 3.6);
 }
+
+#[test]
+fn test_quantiles() {
+    let mut xs: [f64; 0] = [];
+    assert!(quantiles(&mut xs, 4, QuantileMethod::Exclusive).is_err());
+
+    let mut xs = [1.0, 2.0];
+    assert!(quantiles(&mut xs, 0, QuantileMethod::Exclusive).is_err());
+    assert_eq!(quantiles(&mut xs, 4, QuantileMethod::Exclusive).unwrap(), vec![0.75, 1.5, 2.25]);
+
+    let mut xs = [3.0];
+    assert_eq!(quantiles(&mut xs, 3, QuantileMethod::Inclusive).unwrap(), vec![3.0, 3.0]);
+
+    // expected values are taken from python's statistics.quantiles and R's quantile function
+    let expected = [
+        ("exclusive", [2.75, 5.5, 8.25]),
+        ("inclusive", [3.25, 5.5, 7.75]),
+        ("type1", [3.0, 5.0, 8.0]),
+        ("type2", [3.0, 5.5, 8.0]),
+        ("type3", [2.0, 5.0, 8.0]),
+        ("type4", [2.5, 5.0, 7.5]),
+        ("type5", [3.0, 5.5, 8.0]),
+        ("type6", [2.75, 5.5, 8.25]),
+        ("type7", [3.25, 5.5, 7.75]),
+        ("type8", [2.9167, 5.5, 8.0833]),
+        ("type9", [2.9375, 5.5, 8.0625]),
+    ];
+
+    for (method, qs) in expected.iter() {
+        let mut xs = [7.0, 2.0, 9.0, 1.0, 10.0, 4.0, 3.0, 6.0, 8.0, 5.0];
+        let res = quantiles(&mut xs, 4, method.parse().unwrap()).unwrap();
+        let res = res.iter().map(|x| round!(x, 4)).collect::<Vec<f64>>();
+        assert_eq!(&res[..], &qs[..], "method {}", method);
+    }
+
+    assert!("type0".parse::<QuantileMethod>().is_err());
+    assert!("type10".parse::<QuantileMethod>().is_err());
+    assert!("linear".parse::<QuantileMethod>().is_err());
+    assert!("typetype5".parse::<QuantileMethod>().is_err());
+    assert!("type+1".parse::<QuantileMethod>().is_err());
+    assert!("type".parse::<QuantileMethod>().is_err());
+}
+
+#[test]
+fn test_running_stats() {
+    let mut stats = RunningStats::new();
+    assert!(stats.mean().is_err());
+    assert!(stats.min().is_err());
+    assert!(stats.pvariance().is_err());
+
+    stats.push(27.5);
+    assert!(stats.variance().is_err());
+    assert_eq!(stats.pvariance().unwrap(), 0.0);
+
+    stats.extend(&[30.25, 30.25]);
+    stats.extend(&[34.5, 41.75]);
+
+    assert_eq!(stats.count(), 5);
+    assert_eq!(stats.min().unwrap(), 27.5);
+    assert_eq!(stats.max().unwrap(), 41.75);
+    assert_eq!(round!(stats.mean().unwrap(), 4), 32.85);
+    assert_eq!(round!(stats.variance().unwrap(), 4), 31.0188);
+    assert_eq!(round!(stats.pvariance().unwrap(), 4), 24.815);
+    assert_eq!(round!(stats.stdev().unwrap(), 4), 5.5694);
+    assert_eq!(round!(stats.pstdev().unwrap(), 4), 4.9815);
+}
+
+#[test]
+fn test_running_stats_merge() {
+    let xs = [2.75, 1.75, 1.25, 0.25, 0.5, 1.25, 3.5];
+
+    for split in 0..=xs.len() {
+        let (left, right) = xs.split_at(split);
+        let merged = RunningStats::from(left) + RunningStats::from(right);
+
+        assert_eq!(merged.count(), xs.len());
+        assert_eq!(merged.min().unwrap(), 0.25);
+        assert_eq!(merged.max().unwrap(), 3.5);
+        assert_eq!(round!(merged.mean().unwrap(), 4), round!(mean(&xs).unwrap(), 4));
+        assert_eq!(round!(merged.variance().unwrap(), 4), 1.3720);
+    }
+
+    let mut empty = RunningStats::<f64>::new();
+    empty.merge(&RunningStats::new());
+    assert_eq!(empty.count(), 0);
+    assert!(empty.mean().is_err());
+
+    let stats = RunningStats::from(&xs[..]);
+    let restored = RunningStats::from_state(stats.state());
+    assert_eq!(restored.state(), stats.state());
+}
+
+#[test]
+fn test_running_stats_parallel() {
+    let xs = (0..3 * PAR_STATS_CHUNK + 17)
+        .map(|x| ((x * 7919) % 1000) as f64 / 7.0 + 1e6)
+        .collect::<Vec<f64>>();
+
+    let par = running_stats(&xs);
+    let seq = RunningStats::from(&xs[..]);
+
+    assert_eq!(par.count(), seq.count());
+    assert_eq!(par.min().unwrap(), seq.min().unwrap());
+    assert_eq!(par.max().unwrap(), seq.max().unwrap());
+    assert_eq!(round!(par.mean().unwrap(), 6), round!(seq.mean().unwrap(), 6));
+    assert_eq!(round!(par.variance().unwrap(), 6), round!(seq.variance().unwrap(), 6));
+
+    // parallel reduction must be reproducible
+    assert_eq!(running_stats(&xs).state(), par.state());
+}
+
+#[test]
+fn test_accumulators() {
+    let xs = [2.5, 3.0, 10.0, 3.0, 4.0];
+
+    let mut acc = HarmonicMean::default();
+    assert!(acc.harmonic_mean().is_err());
+    for chunk in xs.chunks(2) {
+        acc.update(chunk).unwrap();
+    }
+    assert_eq!(round!(acc.harmonic_mean().unwrap(), 8), round!(harmonic_mean(&xs).unwrap(), 8));
+    assert!(acc.update(&[1.0, -1.0]).is_err());
+
+    let xs = [1, 2, 2, 3, 3, 3, 4];
+
+    let mut acc = ModeCounter::default();
+    assert!(acc.mode().is_err());
+    for chunk in xs.chunks(3) {
+        acc.update(chunk).unwrap();
+    }
+    assert_eq!(acc.mode().unwrap(), 3);
+    assert_eq!(mode(&xs).unwrap(), 3);
+
+    acc.update(&[2]).unwrap();
+    assert!(acc.mode().is_err());
+}
+
+#[test]
+fn test_nan_policy() {
+    let nan = std::f64::NAN;
+    let xs = [1.0, nan, 3.0];
+
+    assert!(NanPolicy::Raise.apply(&xs).is_err());
+    assert_eq!(NanPolicy::Raise.apply(&xs[..1]).unwrap(), (vec![1.0].into(), false));
+
+    let (ys, propagate) = NanPolicy::Omit.apply(&xs).unwrap();
+    assert_eq!((&ys[..], propagate), (&[1.0, 3.0][..], false));
+
+    let mut ys = xs.to_vec();
+    assert!(NanPolicy::Propagate.apply_mut(&mut ys).unwrap());
+    assert!(ys.iter().all(|x| !x.is_nan()));
+    assert_eq!(ys.len(), xs.len());
+
+    // scalar results don't depend on errors of the computation
+    let err = || Err(MyError::NoUniqueMode { modes: 2 });
+    assert!(f64::nan_or_else(true, err).unwrap().is_nan());
+    assert_eq!(f64::nan_or_else(false, || Ok(2.0)).unwrap(), 2.0);
+    let ys = Vec::<f64>::nan_or_else(true, || Ok(vec![1.0, 2.0])).unwrap();
+    assert!(ys.len() == 2 && ys.iter().all(|x| x.is_nan()));
+
+    assert_eq!("omit".parse::<NanPolicy>().unwrap(), NanPolicy::Omit);
+    assert!("ignore".parse::<NanPolicy>().is_err());
+}
+
+#[test]
+fn test_multimode() {
+    let xs: [u32; 0] = [];
+    assert_eq!(multimode(&xs).unwrap(), vec![]);
+
+    let xs = ["b", "a", "c", "a", "b", "d"];
+    assert_eq!(multimode(&xs).unwrap(), vec!["b", "a"]);
+
+    let xs = [3, 1, 2, 2, 1, 3, 3, 2, 5];
+
+    let mut acc = ModeCounter::default();
+    for chunk in xs.chunks(2) {
+        acc.update(chunk).unwrap();
+    }
+
+    assert_eq!(acc.multimode().unwrap(), vec![3, 2]);
+    assert_eq!(multimode(&xs).unwrap(), vec![3, 2]);
+    assert!(acc.mode().is_err());
+}
+
+#[test]
+fn test_value_counts() {
+    let xs = ["b", "a", "c", "a", "b", "d", "a"];
+
+    let counts = value_counts(&xs).unwrap();
+    assert_eq!(counts, vec![("b", 2), ("a", 3), ("c", 1), ("d", 1)]);
+
+    let mut acc = ModeCounter::default();
+    for chunk in xs.chunks(3) {
+        acc.update(chunk).unwrap();
+    }
+    assert_eq!(acc.value_counts().unwrap(), counts);
+
+    assert_eq!(
+        most_common(counts.clone(), None, true),
+        vec![("a", 3), ("b", 2), ("c", 1), ("d", 1)]
+    );
+    assert_eq!(most_common(counts.clone(), Some(3), true), vec![("a", 3), ("b", 2), ("c", 1)]);
+    assert_eq!(most_common(counts.clone(), Some(2), false), vec![("b", 2), ("a", 3)]);
+    assert_eq!(most_common(counts.clone(), None, false), counts);
+    assert_eq!(most_common(counts.clone(), Some(0), true), vec![]);
+
+    let xs: [u32; 0] = [];
+    assert_eq!(value_counts(&xs).unwrap(), vec![]);
+}
+
+#[test]
+fn test_int_mode() {
+    fn sorted_counts(xs: &[i64]) -> Vec<(i64, u64)> {
+        let mut counts = int_counts(xs);
+        counts.sort();
+        counts
+    }
+
+    fn expected_counts(xs: &[i64]) -> Vec<(i64, u64)> {
+        let mut counts = value_counts(xs).unwrap();
+        counts.sort();
+        counts
+    }
+
+    fn prop(xs: Vec<i64>) -> bool {
+        sorted_counts(&xs) == expected_counts(&xs)
+    }
+
+    quickcheck(prop as fn(Vec<i64>) -> bool);
+
+    assert_eq!(int_counts::<u64>(&[]), vec![]);
+    assert_eq!(int_counts(&[u64::max_value(), 0, u64::max_value()]).len(), 2);
+    assert_eq!(
+        int_mode(&[i64::min_value(), i64::max_value(), i64::min_value()]).unwrap(),
+        i64::min_value()
+    );
+    assert!(int_mode::<i64>(&[]).is_err());
+    assert!(int_mode(&[1, 2, 2, 1]).is_err());
+
+    // long inputs are counted in parallel, both with small and large range of values
+    let small_range = (0..5 * PAR_STATS_CHUNK as i64 + 17)
+        .map(|x| x % 101 - 50)
+        .chain(vec![-7; 3])
+        .collect::<Vec<i64>>();
+    assert_eq!(sorted_counts(&small_range), expected_counts(&small_range));
+    assert_eq!(int_mode(&small_range).unwrap(), -7);
+
+    let large_range = (0..5 * PAR_STATS_CHUNK as i64 + 17)
+        .map(|x| (x * 7919) % 1_000_003 * 1_000_000)
+        .chain(vec![42; 3])
+        .collect::<Vec<i64>>();
+    assert_eq!(sorted_counts(&large_range), expected_counts(&large_range));
+    assert_eq!(int_mode(&large_range).unwrap(), 42);
+}
+
+#[test]
+fn test_heavy_hitters() {
+    fn check(hh: &HeavyHitters<u8>, xs: &[u8]) -> bool {
+        let counts = value_counts(xs).unwrap().into_iter().collect::<HashMap<u8, u64>>();
+        let top = hh.top();
+
+        let bounded = top.iter().all(|(x, count, error)| {
+            let freq = counts.get(x).cloned().unwrap_or(0);
+            count - error <= freq && freq <= *count && *error <= xs.len() as u64 / hh.k() as u64
+        });
+
+        // frequent items must be monitored
+        let found = counts
+            .iter()
+            .filter(|(_, n)| **n > xs.len() as u64 / hh.k() as u64)
+            .all(|(x, _)| top.iter().any(|y| y.0 == *x));
+
+        let sorted = top.windows(2).all(|w| w[0].1 >= w[1].1);
+
+        bounded && found && sorted && top.len() <= hh.k() && hh.count() == xs.len() as u64
+    }
+
+    fn prop(xs: Vec<u8>, ys: Vec<u8>, k: usize) -> bool {
+        // skew data a bit, so that there are some frequent items
+        let xs = xs.into_iter().map(|x| x % 32).collect::<Vec<u8>>();
+        let ys = ys.into_iter().map(|x| x / 8).collect::<Vec<u8>>();
+        let k = k % 16 + 1;
+
+        let mut a = HeavyHitters::new(k).unwrap();
+        a.extend(&xs);
+        let mut b = HeavyHitters::new(k).unwrap();
+        b.extend(&ys);
+
+        let valid = check(&a, &xs) && check(&b, &ys);
+
+        a.merge(&b).unwrap();
+        valid && check(&a, &[xs, ys].concat())
+    }
+
+    quickcheck(prop as fn(Vec<u8>, Vec<u8>, usize) -> bool);
+
+    // exact counts if there are no more than k distinct items
+    let mut hh = HeavyHitters::new(3).unwrap();
+    hh.extend(&["a", "b", "a", "c", "a", "b"]);
+    assert_eq!(hh.top(), vec![("a", 3, 0), ("b", 2, 0), ("c", 1, 0)]);
+
+    assert!(HeavyHitters::<u8>::new(0).is_err());
+    assert!(hh.merge(&HeavyHitters::new(2).unwrap()).is_err());
+}
+
+#[test]
+fn test_stable_hash() {
+    use crate::stat_funcs::hashing::hash_with_seed;
+
+    // serialized sketches depend on these values, they must never change
+    assert_eq!(hash_with_seed(&1u64, 0), 0x5ca6_bbcb_b1e8_5355);
+    assert_eq!(hash_with_seed(&1u8, 0), hash_with_seed(&1i64, 0));
+    assert_eq!(hash_with_seed("abc", 7), 0xed50_fd63_fbc6_7dfc);
+    assert_eq!(hash_with_seed("abc", 7), hash_with_seed(&"abc".to_string(), 7));
+}
+
+#[test]
+fn test_count_min_sketch() {
+    fn prop(xs: Vec<u16>, ys: Vec<String>) -> bool {
+        let mut a = CountMinSketch::new(64, 4, 42).unwrap();
+        let mut b = CountMinSketch::new(64, 4, 42).unwrap();
+
+        for x in &xs {
+            a.add(x, 1);
+        }
+        for y in &ys {
+            b.add(y, 2);
+        }
+
+        a.merge(&b).unwrap();
+        let restored = CountMinSketch::from_bytes(&a.to_bytes()).unwrap();
+
+        let xs_ok = value_counts(&xs)
+            .unwrap()
+            .iter()
+            .all(|(x, n)| a.estimate(x) >= *n && restored.estimate(x) == a.estimate(x));
+        let ys_ok = value_counts(&ys)
+            .unwrap()
+            .iter()
+            .all(|(y, n)| a.estimate(y) >= 2 * *n);
+
+        xs_ok && ys_ok && restored.total() == (xs.len() + 2 * ys.len()) as u64
+    }
+
+    quickcheck(prop as fn(Vec<u16>, Vec<String>) -> bool);
+
+    // with a wide table estimates of a small number of items are exact
+    let mut cms = CountMinSketch::new(1 << 12, 5, 0).unwrap();
+    for x in 0..100u64 {
+        cms.add(&x, x);
+    }
+    assert!((0..100u64).all(|x| cms.estimate(&x) == x));
+    assert_eq!(cms.estimate("missing"), 0);
+
+    assert!(CountMinSketch::new(0, 4, 0).is_err());
+    assert!(cms.merge(&CountMinSketch::new(1 << 12, 5, 1).unwrap()).is_err());
+
+    let bytes = cms.to_bytes();
+    assert!(CountMinSketch::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(CountMinSketch::from_bytes(&bytes[1..]).is_err());
+    assert!(CountMinSketch::from_bytes(&[bytes.clone(), vec![0; 8]].concat()).is_err());
+}
+
+#[test]
+fn test_count_distinct() {
+    fn prop(xs: Vec<i64>) -> bool {
+        let expected = value_counts(&xs).unwrap().len();
+        count_distinct(&xs).unwrap() == expected && int_count_distinct(&xs).unwrap() == expected
+    }
+
+    quickcheck(prop as fn(Vec<i64>) -> bool);
+
+    assert_eq!(count_distinct(&["a", "b", "a"]).unwrap(), 2);
+    assert_eq!(count_distinct::<u8>(&[]).unwrap(), 0);
+}
+
+#[test]
+fn test_hyperloglog() {
+    for &precision in &[4, 10, 14] {
+        let std_error = 1.04 / f64::from(1u32 << precision).sqrt();
+
+        for &n in &[0u64, 1, 10, 1000, 100_000] {
+            let mut hll = HyperLogLog::new(precision).unwrap();
+            for x in 0..n {
+                hll.add(&x);
+                // duplicates must not change the estimate
+                hll.add(&x);
+            }
+
+            let estimate = hll.cardinality();
+            assert!((estimate - n as f64).abs() <= 4.0 * std_error * n as f64 + 1.0);
+        }
+    }
+
+    let mut a = HyperLogLog::new(12).unwrap();
+    let mut b = HyperLogLog::new(12).unwrap();
+    let mut union = HyperLogLog::new(12).unwrap();
+
+    for x in 0..30_000u32 {
+        a.add(&x);
+        union.add(&x);
+    }
+    for x in 20_000..50_000u32 {
+        b.add(&x);
+        union.add(&x);
+    }
+
+    a.merge(&b).unwrap();
+    assert_eq!(a.cardinality(), union.cardinality());
+    assert!((a.cardinality() - 50_000.0).abs() < 2500.0);
+
+    let restored = HyperLogLog::from_bytes(&a.to_bytes()).unwrap();
+    assert_eq!(restored.cardinality(), a.cardinality());
+
+    let bytes = a.to_bytes();
+    assert!(HyperLogLog::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(HyperLogLog::from_bytes(&CountMinSketch::new(2, 2, 0).unwrap().to_bytes()).is_err());
+
+    assert!(HyperLogLog::new(3).is_err());
+    assert!(HyperLogLog::new(19).is_err());
+    assert!(HyperLogLog::new(-1).is_err());
+    assert!(HyperLogLog::new(300).is_err());
+    assert!(a.merge(&HyperLogLog::new(11).unwrap()).is_err());
+}
+
+#[test]
+fn test_tdigest() {
+    // exact rank of x in sorted data, ties are counted as half
+    fn rank(xs: &[f64], x: f64) -> f64 {
+        let below = xs.iter().filter(|y| **y < x).count() as f64;
+        let equal = xs.iter().filter(|y| **y == x).count() as f64;
+        (below + equal / 2.0) / xs.len() as f64
+    }
+
+    let n = 100_000;
+    // pseudo-random permutation of exponentially distributed values
+    let xs = (0..n)
+        .map(|i| ((i * 7919) % n) as f64)
+        .map(|x| -(1.0 - x / n as f64).ln() * 100.0)
+        .collect::<Vec<f64>>();
+
+    let mut parts = xs
+        .chunks(n / 7)
+        .map(|chunk| {
+            let mut digest = TDigest::new(100.0).unwrap();
+            digest.extend(chunk).unwrap();
+            digest
+        })
+        .collect::<Vec<TDigest>>();
+
+    let mut digest = parts.remove(0);
+    for part in &parts {
+        digest.merge(part);
+    }
+
+    let mut sorted = xs.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    assert_eq!(digest.count(), n as u64);
+    assert_eq!(digest.quantile(0.0).unwrap(), sorted[0]);
+    assert_eq!(digest.quantile(1.0).unwrap(), sorted[n - 1]);
+    assert_eq!(digest.cdf(-1.0).unwrap(), 0.0);
+    assert_eq!(digest.cdf(sorted[n - 1]).unwrap(), 1.0);
+
+    for &q in &[0.001, 0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 0.999] {
+        // error is measured in ranks, it is smaller near the tails
+        let estimate = digest.quantile(q).unwrap();
+        let tolerance = 0.02 * (q * (1.0 - q)).sqrt() + 1e-4;
+        assert!((rank(&sorted, estimate) - q).abs() <= tolerance);
+
+        let x = sorted[(q * n as f64) as usize];
+        assert!((digest.cdf(x).unwrap() - rank(&sorted, x)).abs() <= tolerance);
+    }
+
+    // estimates of random data never deviate more than by 5% of ranks
+    fn prop(xs: Vec<f64>, ys: Vec<f64>, q: f64) -> TestResult {
+        let finite = xs.iter().chain(&ys).all(|x| x.is_finite());
+
+        if xs.is_empty() || !finite || !(q >= 0.0 && q <= 1.0) {
+            return TestResult::discard();
+        }
+
+        let mut digest = TDigest::new(100.0).unwrap();
+        let mut other = TDigest::new(100.0).unwrap();
+        digest.extend(&xs).unwrap();
+        other.extend(&ys).unwrap();
+        digest.merge(&other);
+
+        let mut sorted = [xs, ys].concat();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // estimates are interpolated between neighbouring values, so one more value on each
+        // side is allowed
+        let n = sorted.len() as f64;
+        let lo = sorted[((q - 0.05) * n - 1.0).max(0.0) as usize];
+        let hi = sorted[((q + 0.05) * n + 1.0).min(n - 1.0) as usize];
+
+        let estimate = digest.quantile(q).unwrap();
+        TestResult::from_bool(lo <= estimate && estimate <= hi)
+    }
+
+    quickcheck(prop as fn(Vec<f64>, Vec<f64>, f64) -> TestResult);
+
+    let mut restored = TDigest::from_bytes(&digest.to_bytes()).unwrap();
+    assert_eq!(restored.count(), digest.count());
+    assert_eq!(restored.quantile(0.3).unwrap(), digest.quantile(0.3).unwrap());
+
+    // small data sets are represented exactly
+    let mut digest = TDigest::new(100.0).unwrap();
+    digest.extend(&[3.0, 1.0, 2.0]).unwrap();
+    assert_eq!(digest.quantile(0.5).unwrap(), 2.0);
+    assert_eq!(digest.cdf(2.0).unwrap(), 0.5);
+
+    assert!(digest.push(std::f64::NAN).is_err());
+    assert!(digest.push(std::f64::INFINITY).is_err());
+    assert!(digest.extend(&[1.0, std::f64::NEG_INFINITY]).is_err());
+    assert_eq!(digest.count(), 3);
+    assert!(digest.quantile(1.5).is_err());
+    assert!(TDigest::new(100.0).unwrap().quantile(0.5).is_err());
+    assert!(TDigest::new(0.5).is_err());
+
+    let bytes = digest.to_bytes();
+    assert!(TDigest::from_bytes(&bytes[..bytes.len() - 8]).is_err());
+
+    // weights whose sum overflows are rejected, centroids follow the tag and four words
+    let mut bytes = bytes;
+    for weight in bytes[36..].chunks_mut(16) {
+        weight[8..].copy_from_slice(&std::u64::MAX.to_le_bytes());
+    }
+    assert!(TDigest::from_bytes(&bytes).is_err());
+}
+
+fn kll_extend<T: PartialOrd + Clone>(sketch: &mut KllSketch<T>, xs: &[T]) {
+    for x in xs {
+        sketch.push(x.clone());
+    }
+}
+
+#[test]
+fn test_kll_sketch() {
+    let n = 100_000u64;
+    // pseudo-random permutation of 0..n
+    let xs = (0..n).map(|i| (i * 7919) % n).collect::<Vec<u64>>();
+
+    let mut parts = xs
+        .chunks(n as usize / 7)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut sketch = KllSketch::new(200, Some(i as u64)).unwrap();
+            kll_extend(&mut sketch, chunk);
+            sketch
+        })
+        .collect::<Vec<KllSketch<u64>>>();
+
+    let mut sketch = parts.remove(0);
+    for part in &parts {
+        sketch.merge(part).unwrap();
+    }
+
+    assert_eq!(sketch.count(), n);
+    assert_eq!(sketch.min().unwrap(), 0);
+    assert_eq!(sketch.max().unwrap(), n - 1);
+    assert_eq!(sketch.quantile(0.0).unwrap(), 0);
+    assert_eq!(sketch.quantile(1.0).unwrap(), n - 1);
+
+    // value x has rank (x + 1) / n
+    let eps = kll_rank_error(sketch.k());
+    for &q in &[0.001, 0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 0.999] {
+        let estimate = sketch.quantile(q).unwrap();
+        assert!(((estimate + 1) as f64 / n as f64 - q).abs() <= eps);
+
+        let x = (q * n as f64) as u64;
+        assert!((sketch.rank(&x).unwrap() - (x + 1) as f64 / n as f64).abs() <= eps);
+    }
+
+    // sketches with the same seed give the same results
+    fn prop(xs: Vec<String>, seed: u64, q: f64) -> TestResult {
+        if xs.is_empty() || !(q >= 0.0 && q <= 1.0) {
+            return TestResult::discard();
+        }
+
+        let mut sketches = (0..2).map(|_| KllSketch::new(8, Some(seed)).unwrap());
+        let (mut a, mut b) = (sketches.next().unwrap(), sketches.next().unwrap());
+
+        for _ in 0..10 {
+            kll_extend(&mut a, &xs);
+            kll_extend(&mut b, &xs);
+        }
+
+        TestResult::from_bool(a.quantile(q).unwrap() == b.quantile(q).unwrap())
+    }
+
+    quickcheck(prop as fn(Vec<String>, u64, f64) -> TestResult);
+
+    // small data sets are represented exactly
+    let mut sketch = KllSketch::new(8, None).unwrap();
+    kll_extend(&mut sketch, &[3.0, 1.0, 2.0, 4.0]);
+    assert_eq!(sketch.quantile(0.5).unwrap(), 2.0);
+    assert_eq!(sketch.quantile(0.6).unwrap(), 3.0);
+    assert_eq!(sketch.rank(&2.5).unwrap(), 0.5);
+
+    assert!(sketch.quantile(1.5).is_err());
+    assert!(sketch.merge(&KllSketch::new(16, None).unwrap()).is_err());
+    assert!(KllSketch::<f64>::new(200, None).unwrap().quantile(0.5).is_err());
+    assert!(KllSketch::<f64>::new(4, None).is_err());
+}
+
+#[test]
+fn test_p2_quantiles() {
+    let n = 100_000;
+    // pseudo-random permutation of exponentially distributed values
+    let xs = (0..n)
+        .map(|i| ((i * 7919) % n) as f64)
+        .map(|x| -(1.0 - x / n as f64).ln())
+        .collect::<Vec<f64>>();
+
+    let ps = [0.99, 0.5, 0.1, 0.9];
+    let mut p2 = P2Quantiles::new(&ps).unwrap();
+    p2.extend(&xs).unwrap();
+
+    assert_eq!(p2.count(), n as u64);
+    assert_eq!(p2.min().unwrap(), 0.0);
+    assert_eq!(p2.max().unwrap(), xs.iter().cloned().fold(0.0, f64::max));
+
+    // quantiles of exponential distribution are -ln(1 - p)
+    for (p, estimate) in ps.iter().zip(p2.quantiles().unwrap()) {
+        let expected = -(1.0 - p).ln();
+        assert!((estimate - expected).abs() <= 0.01 * expected);
+    }
+
+    // single quantile has 5 markers, fewer values are handled exactly
+    let mut p2 = P2Quantiles::new(&[0.5]).unwrap();
+    p2.extend(&[4.0, 1.0, 3.0, 2.0]).unwrap();
+    assert_eq!(p2.quantiles().unwrap(), vec![2.5]);
+
+    // estimates always stay within the range of data
+    fn prop(xs: Vec<f64>, p: f64) -> TestResult {
+        if xs.is_empty() || xs.iter().any(|x| !x.is_finite()) || !(p > 0.0 && p < 1.0) {
+            return TestResult::discard();
+        }
+
+        let mut p2 = P2Quantiles::new(&[p]).unwrap();
+        p2.extend(&xs).unwrap();
+
+        let estimate = p2.quantiles().unwrap()[0];
+        let (min, max) = (p2.min().unwrap(), p2.max().unwrap());
+
+        TestResult::from_bool(min <= estimate && estimate <= max)
+    }
+
+    quickcheck(prop as fn(Vec<f64>, f64) -> TestResult);
+
+    assert!(p2.push(std::f64::NAN).is_err());
+    assert!(P2Quantiles::new(&[]).is_err());
+    assert!(P2Quantiles::new(&[0.5]).unwrap().quantiles().is_err());
+    assert!(P2Quantiles::new(&[1.0]).is_err());
+}
+
+#[test]
+fn test_gk_summary() {
+    // checks that x may have rank within epsilon * n of phi * n in sorted data
+    fn is_valid(sorted: &[f64], x: f64, phi: f64, epsilon: f64) -> bool {
+        let n = sorted.len() as f64;
+        let lo = sorted.iter().filter(|y| **y < x).count() as f64 + 1.0;
+        let hi = sorted.iter().filter(|y| **y <= x).count() as f64;
+        let rank = (phi * n).ceil();
+        lo - epsilon * n <= rank && rank <= hi + epsilon * n
+    }
+
+    let n = 100_000;
+    // pseudo-random permutation of 0..n
+    let xs = (0..n).map(|i| ((i * 7919) % n) as f64).collect::<Vec<f64>>();
+
+    let mut parts = xs
+        .chunks(n / 7)
+        .map(|chunk| {
+            let mut summary = GKSummary::new(0.001).unwrap();
+            summary.extend(chunk);
+            summary
+        })
+        .collect::<Vec<GKSummary<f64>>>();
+
+    let mut summary = parts.remove(0);
+    for part in &parts {
+        summary.merge(part).unwrap();
+    }
+
+    assert_eq!(summary.count(), n as u64);
+    assert!(summary.size() < n / 10);
+    assert_eq!(summary.min().unwrap(), 0.0);
+    assert_eq!(summary.max().unwrap(), (n - 1) as f64);
+    assert_eq!(summary.query(0.0).unwrap(), 0.0);
+    assert_eq!(summary.query(1.0).unwrap(), (n - 1) as f64);
+
+    // extremes are exact even if epsilon allows returning other values
+    let mut coarse = GKSummary::new(0.25).unwrap();
+    coarse.extend(&xs[..1000]);
+    assert_eq!(coarse.query(0.0).unwrap(), coarse.min().unwrap());
+    assert_eq!(coarse.query(1.0).unwrap(), coarse.max().unwrap());
+
+    let mut sorted = xs.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    for &phi in &[0.001, 0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99, 0.999] {
+        assert!(is_valid(&sorted, summary.query(phi).unwrap(), phi, 0.001));
+
+        // value x has rank (x + 1) / n
+        let x = (phi * n as f64).floor();
+        assert!((summary.rank(&x).unwrap() - (x + 1.0) / n as f64).abs() <= 0.001);
+    }
+
+    let restored = GKSummary::<f64>::from_bytes(&summary.to_bytes()).unwrap();
+    assert_eq!(restored.count(), summary.count());
+    assert_eq!(restored.query(0.3).unwrap(), summary.query(0.3).unwrap());
+
+    // error guarantee holds for any data, including merged summaries
+    fn prop(xs: Vec<i32>, ys: Vec<i32>, phi: f64) -> TestResult {
+        if xs.is_empty() || !(phi >= 0.0 && phi <= 1.0) {
+            return TestResult::discard();
+        }
+
+        let epsilon = 0.05;
+        let mut summary = GKSummary::new(epsilon).unwrap();
+        let mut other = GKSummary::new(epsilon).unwrap();
+
+        for x in &xs {
+            summary.insert(f64::from(x % 20));
+        }
+        for y in &ys {
+            other.insert(f64::from(y % 20));
+        }
+        summary.merge(&other).unwrap();
+
+        let mut sorted = xs.iter().chain(&ys).map(|x| f64::from(x % 20)).collect::<Vec<_>>();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let x = summary.query(phi).unwrap();
+        let true_rank = sorted.iter().filter(|y| **y <= x).count() as f64 / sorted.len() as f64;
+        let lo_rank = sorted.iter().filter(|y| **y < x).count() as f64 / sorted.len() as f64;
+        let rank = summary.rank(&x).unwrap();
+
+        TestResult::from_bool(
+            is_valid(&sorted, x, phi, epsilon)
+                && rank <= true_rank + epsilon
+                && rank >= lo_rank - epsilon,
+        )
+    }
+
+    quickcheck(prop as fn(Vec<i32>, Vec<i32>, f64) -> TestResult);
+
+    let mut summary = GKSummary::new(0.01).unwrap();
+    summary.extend(&["b", "c", "a"]);
+    assert_eq!(summary.query(0.5).unwrap(), "b");
+
+    assert!(summary.query(1.5).is_err());
+    assert!(summary.merge(&GKSummary::new(0.1).unwrap()).is_err());
+    assert!(GKSummary::<f64>::new(0.01).unwrap().query(0.5).is_err());
+    assert!(GKSummary::<f64>::new(0.0).is_err());
+
+    let bytes = restored.to_bytes();
+    assert!(GKSummary::<f64>::from_bytes(&bytes[..bytes.len() - 8]).is_err());
+}
+
+#[test]
+fn test_sampling() {
+    fn prop(n: usize, k: usize, seed: u64) -> TestResult {
+        if k > n {
+            return TestResult::from_bool(sample_indices(n, k, Some(seed)).is_err());
+        }
+
+        let sample = sample_indices(n, k, Some(seed)).unwrap();
+        let distinct = sample.iter().collect::<std::collections::HashSet<_>>();
+
+        TestResult::from_bool(
+            sample.len() == k
+                && distinct.len() == k
+                && sample.iter().all(|idx| *idx < n)
+                && sample == sample_indices(n, k, Some(seed)).unwrap(),
+        )
+    }
+
+    quickcheck(prop as fn(usize, usize, u64) -> TestResult);
+
+    // each index of 0..10 is chosen with probability 3 / 10
+    let mut counts = vec![0; 10];
+    for seed in 0..10_000 {
+        for idx in sample_indices(10, 3, Some(seed)).unwrap() {
+            counts[idx] += 1;
+        }
+    }
+    assert!(counts.iter().all(|c| (*c as f64 - 3000.0).abs() < 200.0));
+
+    // the first drawn index is chosen with probability proportional to its weight
+    let weights = [1.0, 0.0, 2.0, 5.0, 2.0];
+    let mut counts = vec![0; 5];
+    for seed in 0..10_000 {
+        let sample = weighted_sample_indices(&weights, 3, Some(seed)).unwrap();
+        assert_eq!(sample.len(), 3);
+        assert!(!sample.contains(&1));
+        counts[sample[0]] += 1;
+    }
+    for (c, w) in counts.iter().zip(&weights) {
+        assert!((*c as f64 - 1000.0 * w).abs() < 200.0);
+    }
+
+    assert!(weighted_sample_indices(&weights, 5, None).is_err());
+    assert!(weighted_sample_indices(&[1.0, -1.0], 1, None).is_err());
+    assert!(weighted_sample_indices(&[1.0, std::f64::NAN], 1, None).is_err());
+
+    // each item of the stream gets into the reservoir with probability k / n
+    let mut counts = vec![0; 100];
+    for seed in 0..10_000 {
+        let mut reservoir = Reservoir::new(5, Some(seed)).unwrap();
+        reservoir.extend(&(0..100).collect::<Vec<usize>>());
+        assert_eq!(reservoir.count(), 100);
+        assert_eq!(reservoir.sample().len(), 5);
+        for idx in reservoir.sample() {
+            counts[*idx] += 1;
+        }
+    }
+    assert!(counts.iter().all(|c| (*c as f64 - 500.0).abs() < 100.0));
+
+    let mut reservoir = Reservoir::new(5, None).unwrap();
+    reservoir.extend(&[1, 2, 3]);
+    assert_eq!(reservoir.sample(), &[1, 2, 3]);
+    assert!(Reservoir::<i32>::new(0, None).is_err());
+}
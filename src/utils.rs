@@ -1,7 +1,15 @@
-use num::Float;
+use num::{Float, NumCast};
 
-use cpython::{FromPyObject, PyObject, PyResult, Python};
+use cpython::buffer::PyBuffer;
+use cpython::{exc, FromPyObject, ObjectProtocol, PyDict, PyErr, PyList, PyObject, PyResult,
+              PySequence, PyTuple, Python, PythonObject, ToPyObject};
+use crate::stat_funcs::errors::{to_python_result, MyError};
+use crate::stat_funcs::{most_common, NanPolicy, QuantileMethod};
 use ordered_float::{NotNaN, OrderedFloat};
+use std::slice;
+
+// number of elements extracted from python iterator before passing them to rust accumulator
+const STREAM_CHUNK: usize = 4096;
 
 #[inline]
 crate fn pylist_to_vec<T>(py: Python<'_>, xs: &PyObject) -> PyResult<Vec<T>>
@@ -11,6 +19,185 @@ where
     Vec::extract(py, &xs)
 }
 
+/// Collect elements of any python iterable (generator, map object, dict view, etc.), sequences
+/// are extracted directly
+crate fn iter_to_vec<T>(py: Python<'_>, xs: &PyObject) -> PyResult<Vec<T>>
+where
+    for<'a> T: FromPyObject<'a>,
+{
+    if xs.cast_as::<PySequence>(py).is_ok() {
+        return pylist_to_vec(py, xs);
+    }
+
+    xs.iter(py)?.map(|x| x?.extract(py)).collect()
+}
+
+/// Lists, tuples and objects supporting buffer protocol are already stored in memory as a
+/// whole, so there is no point in processing them chunk by chunk
+crate fn is_in_memory(py: Python<'_>, xs: &PyObject) -> bool {
+    xs.cast_as::<PyList>(py).is_ok()
+        || xs.cast_as::<PyTuple>(py).is_ok()
+        || PyBuffer::get(py, xs).is_ok()
+}
+
+/// Consume python iterable chunk by chunk, passing each chunk to f, so that the whole sequence
+/// is never materialized
+crate fn for_each_chunk<T, F>(py: Python<'_>, xs: &PyObject, mut f: F) -> PyResult<()>
+where
+    for<'a> T: FromPyObject<'a>,
+    F: FnMut(&[T]) -> Result<(), MyError>,
+{
+    let mut chunk = Vec::with_capacity(STREAM_CHUNK);
+
+    for x in xs.iter(py)? {
+        chunk.push(x?.extract(py)?);
+
+        if chunk.len() == STREAM_CHUNK {
+            to_python_result(py, f(&chunk))?;
+            chunk.clear();
+        }
+    }
+
+    to_python_result(py, f(&chunk))
+}
+
+/// Input data of a statistical function, it is either borrowed directly from the memory of an
+/// object supporting buffer protocol (array.array, numpy array, memoryview, etc.) or extracted
+/// element by element from any other python iterable.
+///
+/// Exported buffer can't be resized or freed until it is released, which happens on drop, but
+/// its elements may still be modified by other python threads while a function runs without
+/// the GIL, the result is unspecified in this case
+crate enum Samples<T> {
+    Buffer {
+        _buf: PyBuffer,
+        ptr: *const T,
+        len: usize,
+    },
+    Owned(Vec<T>),
+}
+
+impl<T: Clone> Samples<T> {
+    #[inline]
+    crate fn as_slice(&self) -> &[T] {
+        match self {
+            Samples::Buffer { ptr, len, .. } => unsafe { slice::from_raw_parts(*ptr, *len) },
+            Samples::Owned(xs) => xs,
+        }
+    }
+
+    /// Mutating algorithms need their own copy of the data, buffer contents are copied only in
+    /// this case, must be called while the GIL is held
+    #[inline]
+    crate fn into_vec(self) -> Vec<T> {
+        match self {
+            Samples::Buffer { .. } => self.as_slice().to_vec(),
+            Samples::Owned(xs) => xs,
+        }
+    }
+}
+
+crate trait FromPySamples: Sized {
+    fn extract_samples(py: Python<'_>, xs: &PyObject) -> PyResult<Samples<Self>>;
+}
+
+/// Buffer of a different numeric type is converted element by element, only conversions which
+/// keep the values are done: ints are converted to floats or to ints wide enough to hold them,
+/// floats are converted only to floats. Ints which can't be represented exactly by T (ints
+/// above 2^53 converted to floats, for example) raise OverflowError. Returns None if the buffer
+/// has unsupported format
+fn convert_buffer<T: NumCast + Copy>(
+    py: Python<'_>,
+    buf: &PyBuffer,
+    float_target: bool,
+) -> PyResult<Option<Vec<T>>> {
+    macro_rules! try_convert {
+        (floats: $($src:ty),+) => {
+            $(
+                // floats are converted only to wider floats, which is always exact
+                if let Some(cells) = buf.as_slice::<$src>(py) {
+                    return Ok(cells.iter().map(|x| T::from(x.get())).collect());
+                }
+            )+
+        };
+        (ints: $($src:ty),+) => {
+            $(
+                if let Some(cells) = buf.as_slice::<$src>(py) {
+                    let mut ys = Vec::with_capacity(cells.len());
+
+                    for x in cells.iter().map(|x| x.get()) {
+                        // conversion is exact only if the value survives the round trip
+                        match T::from(x).filter(|y| <$src as NumCast>::from(*y) == Some(x)) {
+                            Some(y) => ys.push(y),
+                            None => {
+                                return Err(PyErr::new::<exc::OverflowError, _>(
+                                    py,
+                                    format!("buffer value {} can't be converted exactly", x),
+                                ))
+                            }
+                        }
+                    }
+
+                    return Ok(Some(ys));
+                }
+            )+
+        };
+    }
+
+    if buf.as_slice::<f64>(py).is_some() || buf.as_slice::<f32>(py).is_some() {
+        if !float_target {
+            return Err(PyErr::new::<exc::TypeError, _>(
+                py,
+                "can't use a buffer of floats as input of a function expecting ints",
+            ));
+        }
+        try_convert!(floats: f64, f32);
+    }
+
+    try_convert!(ints: i64, u64, i32, u32, i16, u16, i8, u8);
+
+    Ok(None)
+}
+
+macro_rules! impl_from_py_samples {
+    ($($t:ty => $float_target:expr),+) => {
+        $(
+            impl FromPySamples for $t {
+                fn extract_samples(py: Python<'_>, xs: &PyObject) -> PyResult<Samples<$t>> {
+                    if let Ok(buf) = PyBuffer::get(py, xs) {
+                        // zero copy path, buffer must be contiguous and have exactly the same
+                        // element type
+                        if let Some((ptr, len)) =
+                            buf.as_slice::<$t>(py).map(|ys| (ys.as_ptr() as *const $t, ys.len()))
+                        {
+                            return Ok(Samples::Buffer { _buf: buf, ptr, len });
+                        }
+
+                        if let Some(ys) = convert_buffer(py, &buf, $float_target)? {
+                            return Ok(Samples::Owned(ys));
+                        }
+                    }
+
+                    iter_to_vec(py, xs).map(Samples::Owned)
+                }
+            }
+        )+
+    };
+}
+
+impl_from_py_samples!(f64 => true, i64 => false, u64 => false);
+
+impl FromPySamples for String {
+    fn extract_samples(py: Python<'_>, xs: &PyObject) -> PyResult<Samples<String>> {
+        iter_to_vec(py, xs).map(Samples::Owned)
+    }
+}
+
+#[inline]
+crate fn extract_samples<T: FromPySamples>(py: Python<'_>, xs: &PyObject) -> PyResult<Samples<T>> {
+    T::extract_samples(py, xs)
+}
+
 #[inline]
 crate fn into_mut_notnans<T>(xs: &mut [T]) -> &mut [NotNaN<T>]
 where
@@ -20,14 +207,106 @@ where
 }
 
 #[inline]
-crate fn extract_ordered_floats<T>(py: Python<'_>, xs: &PyObject) -> PyResult<Vec<OrderedFloat<T>>>
+crate fn as_ordered_floats<T>(xs: &[T]) -> &[OrderedFloat<T>]
 where
-    for<'a> T: Float + FromPyObject<'a>,
+    T: Float,
 {
-    let tmp = &Vec::extract(py, &xs)?[..];
-    let ys = unsafe { &*(tmp as *const [T] as *const [ordered_float::OrderedFloat<T>]) };
+    unsafe { &*(xs as *const [T] as *const [ordered_float::OrderedFloat<T>]) }
+}
+
+#[inline]
+crate fn into_ordered_floats<T>(xs: Vec<T>) -> Vec<OrderedFloat<T>>
+where
+    T: Float,
+{
+    xs.into_iter().map(OrderedFloat).collect()
+}
+
+/// Conversion of results computed on ordered floats back to plain floats
+crate trait IntoFloats {
+    type Output;
+
+    fn into_floats(self) -> Self::Output;
+}
+
+impl<T: Float> IntoFloats for OrderedFloat<T> {
+    type Output = T;
+
+    #[inline]
+    fn into_floats(self) -> T {
+        self.into_inner()
+    }
+}
+
+impl<T: Float> IntoFloats for Vec<OrderedFloat<T>> {
+    type Output = Vec<T>;
 
-    Ok(ys.to_vec())
+    fn into_floats(self) -> Vec<T> {
+        self.into_iter().map(|x| x.into_inner()).collect()
+    }
+}
+
+impl<T: Float> IntoFloats for Vec<(OrderedFloat<T>, u64)> {
+    type Output = Vec<(T, u64)>;
+
+    fn into_floats(self) -> Vec<(T, u64)> {
+        self.into_iter().map(|(x, n)| (x.into_inner(), n)).collect()
+    }
+}
+
+/// Convert (value, frequency) pairs into a python list of (value, frequency) tuples if `sort`
+/// is set or into a python dict otherwise, see stat_funcs::most_common for the meaning of `top`.
+///
+/// Frequencies are divided by the total number of values if `normalize` is set
+crate fn counts_to_py<T: ToPyObject>(
+    py: Python<'_>,
+    counts: Vec<(T, u64)>,
+    sort: bool,
+    top: Option<usize>,
+    normalize: bool,
+) -> PyResult<PyObject> {
+    let total = counts.iter().map(|x| x.1).sum::<u64>() as f64;
+    let counts = most_common(counts, top, sort);
+
+    let freq = |n: u64| -> PyObject {
+        if normalize {
+            (n as f64 / total).to_py_object(py).into_object()
+        } else {
+            n.to_py_object(py).into_object()
+        }
+    };
+
+    if sort {
+        let pairs = counts
+            .into_iter()
+            .map(|(x, n)| PyTuple::new(py, &[x.to_py_object(py).into_object(), freq(n)]))
+            .map(|t| t.into_object())
+            .collect::<Vec<PyObject>>();
+
+        Ok(PyList::new(py, &pairs).into_object())
+    } else {
+        let dict = PyDict::new(py);
+
+        for (x, n) in counts {
+            dict.set_item(py, x, freq(n))?;
+        }
+
+        Ok(dict.into_object())
+    }
+}
+
+impl<'source> FromPyObject<'source> for QuantileMethod {
+    fn extract(py: Python<'_>, obj: &'source PyObject) -> PyResult<Self> {
+        let method = String::extract(py, obj)?;
+        to_python_result(py, method.parse())
+    }
+}
+
+impl<'source> FromPyObject<'source> for NanPolicy {
+    fn extract(py: Python<'_>, obj: &'source PyObject) -> PyResult<Self> {
+        let policy = String::extract(py, obj)?;
+        to_python_result(py, policy.parse())
+    }
 }
 
 #[test]
@@ -44,3 +323,12 @@ fn test_extract_notnan() {
         ]
     );
 }
+
+#[test]
+fn test_ordered_floats() {
+    let xs = vec![1.0, 2.5, 3.7];
+    let expected = [OrderedFloat(1.0), OrderedFloat(2.5), OrderedFloat(3.7)];
+
+    assert_eq!(as_ordered_floats(&xs), expected);
+    assert_eq!(into_ordered_floats(xs), expected);
+}
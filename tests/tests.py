@@ -4,8 +4,12 @@
 '''
 
 from hypothesis import given, reproduce_failure
-from hypothesis.strategies import lists, floats, integers, data
-from math import isclose
+from hypothesis.strategies import (lists, floats, integers, data, text, binary, tuples, one_of,
+                                   sampled_from, booleans)
+from array import array
+from collections import Counter
+from math import ceil, copysign, isclose, isnan, sqrt
+import pickle
 import fast_stat
 import statistics
 
@@ -23,6 +27,119 @@ def test_kth_stat(data):
     assert fast_stat.kth_elem_float(xs, k) == sorted(xs)[k]
 
 
+@given(data())
+def test_kth_elems(data):
+    xs = data.draw(lists(floats(allow_nan=False, allow_infinity=False), min_size=1))
+    ks = data.draw(lists(integers(min_value=0, max_value=len(xs)-1)))
+    ys = sorted(xs)
+    assert fast_stat.kth_elems_float(xs, ks) == [ys[k] for k in ks]
+
+
+@given(lists(sampled_from([0.0, -0.0, 1.0]), min_size=1),
+       integers(min_value=0, max_value=2**64 - 1))
+def test_kth_seed(xs, seed):
+    def bits(x):
+        return copysign(1.0, x), x
+    ks = list(range(len(xs)))
+    expected = [bits(x) for x in fast_stat.kth_elems_float(xs, ks, seed=seed)]
+    assert [bits(x) for x in fast_stat.kth_elems_float(xs, ks, seed=seed)] == expected
+    assert bits(fast_stat.kth_elem_float(xs, 0, seed=seed)) == \
+        bits(fast_stat.kth_elem_float(xs, 0, seed=seed))
+    fast_stat.seed(seed)
+    try:
+        assert [bits(x) for x in fast_stat.kth_elems_float(xs, ks)] == expected
+    finally:
+        # the global seed must not leak into other tests even if the assertion fails
+        fast_stat.seed()
+
+
+@given(data())
+def test_radix_select(data):
+    strategy, kth_elem, kth_elems = data.draw(sampled_from([
+        (integers(min_value=-2**63, max_value=2**63 - 1), fast_stat.kth_elem_int,
+         fast_stat.kth_elems_int),
+        (integers(min_value=0, max_value=2**64 - 1), fast_stat.kth_elem_uint,
+         fast_stat.kth_elems_uint),
+        (floats(allow_nan=False), fast_stat.kth_elem_float, fast_stat.kth_elems_float),
+    ]))
+    # inputs are repeated to make them long enough to be processed by radix selection
+    xs = data.draw(lists(strategy, min_size=1, max_size=100))
+    xs = xs * (2**14 // len(xs) + 1)
+    ys = sorted(xs)
+    ks = data.draw(lists(integers(min_value=0, max_value=len(xs)-1), min_size=1, max_size=10))
+    assert kth_elems(xs, ks) == [ys[k] for k in ks]
+    assert kth_elem(xs, ks[0]) == ys[ks[0]]
+    assert fast_stat.kth_elem(xs, ks[0]) == ys[ks[0]]
+
+    xs = data.draw(lists(floats(allow_nan=False, allow_infinity=False), min_size=1, max_size=100))
+    xs = xs * (2**14 // len(xs) + 1) + xs[:1]
+    assert fast_stat.median(xs) == statistics.median(xs)
+    assert fast_stat.median_low(xs) == statistics.median_low(xs)
+    assert fast_stat.median_high(xs) == statistics.median_high(xs)
+
+
+@given(data())
+def test_kth_elem(data):
+    strategy = data.draw(sampled_from([
+        integers(), integers(min_value=2**63), floats(allow_nan=False), text(), binary(),
+        one_of(integers(), floats(allow_nan=False)), one_of(booleans(), integers()),
+        tuples(integers(), text()),
+    ]))
+    xs = data.draw(lists(strategy, min_size=1))
+    k = data.draw(integers(min_value=0, max_value=len(xs)-1))
+    assert fast_stat.kth_elem(xs, k) == sorted(xs)[k]
+
+
+def test_kth_elem_mixed_nan():
+    xs = [1, float('nan'), 0.5, True]
+    assert fast_stat.kth_elem(xs, 0, nan_policy='omit') == 0.5
+    assert fast_stat.kth_elem(xs, 2, nan_policy='omit') == 1
+    assert isnan(fast_stat.kth_elem(xs, 0))
+    try:
+        fast_stat.kth_elem(xs, 0, nan_policy='raise')
+    except Exception:
+        pass
+    else:
+        assert False
+
+
+@given(lists(one_of(integers(min_value=-5, max_value=5), text(max_size=1), tuples(integers(0, 3)),
+                   booleans()),
+             min_size=1))
+def test_mode(xs):
+    try:
+        m = fast_stat.mode(xs)
+    except Exception:
+        # fast_stat.mode raises if there is no unique mode
+        return
+    assert m == statistics.mode(xs)
+    assert type(m) is type(statistics.mode(xs))
+
+
+@given(lists(integers(min_value=-5, max_value=5)), lists(text(max_size=1)))
+def test_multimode(xs, ys):
+    assert fast_stat.multimode_int(xs) == statistics.multimode(xs)
+    assert fast_stat.multimode_float(map(float, xs)) == statistics.multimode(map(float, xs))
+    assert fast_stat.multimode_str(ys) == statistics.multimode(ys)
+
+
+@given(lists(integers(min_value=-5, max_value=5)), lists(text(max_size=1)),
+       one_of(integers(min_value=0, max_value=5), sampled_from([None])))
+def test_value_counts(xs, ys, top):
+    for f, data in [(fast_stat.value_counts_int, xs),
+                    (fast_stat.value_counts_float, [float(x) for x in xs]),
+                    (fast_stat.value_counts_str, ys)]:
+        counter = Counter(data)
+
+        assert f(data) == counter.most_common()
+        assert f(iter(data), top=top) == counter.most_common(top)
+        assert f(data, sort=False) == dict(counter)
+        assert list(f(data, sort=False)) == list(counter)
+
+        expected = {x: n / len(data) for x, n in counter.most_common(top)}
+        assert f(data, top=top, sort=False, normalize=True) == expected
+
+
 @given(lists(floats(allow_nan=False, allow_infinity=False), min_size=1))
 def test_median(xs):
     assert fast_stat.median(xs) == statistics.median(xs)
@@ -45,6 +162,296 @@ def test_median_grouped(data):
     assert isclose(fast_stat.median_grouped(xs, interval), statistics.median_grouped(xs, interval)) is True
 
 
+@given(lists(floats(allow_nan=False, allow_infinity=False, width=32), min_size=2))
+def test_buffer_input(xs):
+    for buf in (array('d', xs), memoryview(array('d', xs)), array('f', xs)):
+        assert fast_stat.variance(buf) == fast_stat.variance(list(buf))
+        assert fast_stat.median(buf) == fast_stat.median(list(buf))
+    ys = [int(x) for x in xs]
+    assert fast_stat.median_grouped(array('q', ys), 1) == fast_stat.median_grouped(ys, 1)
+    assert fast_stat.multimode_int(array('b', [y % 100 for y in ys])) == \
+        fast_stat.multimode_int([y % 100 for y in ys])
+    try:
+        fast_stat.multimode_int(array('d', xs))
+        assert False
+    except TypeError:
+        pass
+    try:
+        fast_stat.mean(array('q', [2**53 + 1]))
+        assert False
+    except OverflowError:
+        pass
+
+
+@given(lists(lists(one_of(integers(min_value=0, max_value=20), text(max_size=1)))),
+       integers(min_value=1, max_value=10))
+def test_heavy_hitters(xss, k):
+    partial = []
+    for xs in xss:
+        hh = fast_stat.HeavyHitters(k)
+        hh.update(iter(xs))
+        partial.append(hh)
+    merged = fast_stat.HeavyHitters(k)
+    for hh in partial:
+        merged.merge(hh)
+    xs = [x for xs in xss for x in xs]
+    counter = Counter(xs)
+    top = merged.top()
+    assert merged.count() == len(xs)
+    assert len(top) <= k
+    for x, count, error in top:
+        assert count - error <= counter[x] <= count
+        assert error <= len(xs) // k
+    for x, n in counter.items():
+        if n > len(xs) // k:
+            assert x in [y for y, _, _ in top]
+    assert merged.top(2) == top[:2]
+    # equal numbers are the same item, just like dict keys
+    hh = fast_stat.HeavyHitters(2)
+    hh.update([1, 1.0, True, 2.5, 2.5])
+    assert hh.top() == [(1, 3, 0), (2.5, 2, 0)]
+
+
+@given(lists(lists(one_of(integers(min_value=-10, max_value=10), floats(allow_nan=False),
+                          text(max_size=2)))),
+       integers(min_value=0, max_value=2**64 - 1))
+def test_count_min_sketch(xss, seed):
+    merged = fast_stat.CountMinSketch(256, 4, seed)
+    for xs in xss:
+        cms = fast_stat.CountMinSketch(256, 4, seed)
+        cms.update(iter(xs))
+        merged.merge(pickle.loads(pickle.dumps(cms)))
+    merged = fast_stat.CountMinSketch.from_bytes(merged.to_bytes())
+    xs = [x for xs in xss for x in xs]
+    assert merged.total() == len(xs)
+    for x, n in Counter(xs).items():
+        assert merged.estimate(x) >= n
+
+
+@given(one_of(lists(integers()), lists(floats(allow_nan=False)), lists(text()), lists(binary()),
+              lists(one_of(integers(), text())), lists(one_of(integers(), floats(allow_nan=False))),
+              lists(one_of(booleans(), integers(min_value=-2, max_value=2)))))
+def test_count_distinct(xs):
+    assert fast_stat.count_distinct(xs) == len(set(xs))
+    # distinct integers above 2**53 are equal as floats
+    assert fast_stat.count_distinct([2**53, 2**53 + 1, 0.5]) == 3
+
+
+@given(lists(lists(one_of(integers(min_value=-2**63, max_value=2**63 - 1), floats(allow_nan=False),
+                          text()))),
+       integers(min_value=4, max_value=18))
+def test_hyperloglog(xss, precision):
+    merged = fast_stat.HyperLogLog(precision)
+    for xs in xss:
+        hll = fast_stat.HyperLogLog(precision)
+        hll.update(iter(xs))
+        merged.merge(pickle.loads(pickle.dumps(hll)))
+    merged = fast_stat.HyperLogLog.from_bytes(merged.to_bytes())
+    n = len(set(x for xs in xss for x in xs))
+    # the estimate deviates from n by more than 5 standard errors with negligible probability
+    assert abs(merged.cardinality() - n) <= 5 * 1.04 / sqrt(2**precision) * n + 1
+    for p in (-1, 3, 19, 300):
+        try:
+            fast_stat.HyperLogLog(p)
+        except Exception as e:
+            assert type(e).__name__ == 'StatisticsError'
+        else:
+            assert False
+
+
+@given(lists(lists(floats(allow_nan=False, allow_infinity=False, min_value=-1e6, max_value=1e6),
+                min_size=1), min_size=1),
+       floats(min_value=0.0, max_value=1.0))
+def test_tdigest(xss, q):
+    merged = fast_stat.TDigest()
+    for xs in xss:
+        digest = fast_stat.TDigest()
+        digest.update(iter(xs))
+        merged.merge(pickle.loads(pickle.dumps(digest)))
+    merged = fast_stat.TDigest.from_bytes(merged.to_bytes())
+    xs = sorted(x for xs in xss for x in xs)
+    assert merged.count() == len(xs)
+    assert merged.min() == xs[0] and merged.max() == xs[-1]
+    # rank of the estimate deviates from q by no more than 5%
+    estimate = merged.quantile(q)
+    lo = xs[int(max(q - 0.05, 0.0) * len(xs))]
+    hi = xs[min(int((q + 0.05) * len(xs)), len(xs) - 1)]
+    assert lo <= estimate <= hi or isclose(estimate, lo) or isclose(estimate, hi)
+    assert 0.0 <= merged.cdf(estimate) <= 1.0
+    for x in (float('inf'), float('-inf'), float('nan')):
+        try:
+            merged.push(x)
+        except Exception:
+            pass
+        else:
+            assert False
+    assert merged.count() == len(xs)
+
+
+@given(lists(lists(integers(min_value=-10**6, max_value=10**6), min_size=1), min_size=1),
+       floats(min_value=0.0, max_value=1.0), integers(min_value=0, max_value=2**64 - 1))
+def test_kll_sketch(xss, q, seed):
+    def build(xss):
+        merged = fast_stat.KllSketch(k=50, seed=seed)
+        for xs in xss:
+            sketch = fast_stat.KllSketch(k=50, seed=seed)
+            sketch.update(iter(xs))
+            merged.merge(sketch)
+        return merged
+    merged = build(xss)
+    xs = sorted(x for xs in xss for x in xs)
+    assert merged.count() == len(xs)
+    assert merged.min() == xs[0] and merged.max() == xs[-1]
+    # seeded sketches are reproducible
+    assert build(xss).quantile(q) == merged.quantile(q)
+    # rank of the estimate deviates from q by no more than 3 times the error bound
+    estimate = merged.quantile(q)
+    lo = xs[int(max(q - 3 * merged.rank_error(), 0.0) * len(xs))]
+    hi = xs[min(int((q + 3 * merged.rank_error()) * len(xs)), len(xs) - 1)]
+    assert lo <= estimate <= hi
+    assert 0.0 < merged.rank(estimate) <= 1.0
+    # ints are converted to floats
+    merged.push(0.5)
+    assert isinstance(merged.quantile(q), float)
+
+
+@given(lists(floats(allow_nan=False, allow_infinity=False, width=32), min_size=1),
+       lists(floats(min_value=0.01, max_value=0.99), min_size=1, max_size=5))
+def test_p2_quantiles(xs, ps):
+    single = fast_stat.P2Quantile(ps[0])
+    for x in xs:
+        single.push(x)
+    multi = fast_stat.P2Quantiles(ps)
+    multi.update(iter(xs))
+    assert single.count() == multi.count() == len(xs)
+    assert single.min() == multi.min() == min(xs)
+    assert single.max() == multi.max() == max(xs)
+    estimates = multi.quantiles()
+    assert len(estimates) == len(ps)
+    assert all(min(xs) <= q <= max(xs) for q in estimates + [single.quantile()])
+    if len(xs) < 5:
+        # few values are handled exactly
+        assert isclose(single.quantile(), numpy_quantile(sorted(xs), ps[0]), abs_tol=1e-6)
+
+
+def numpy_quantile(xs, p):
+    # quantile of sorted data with linear interpolation, the default method of numpy
+    rank = p * (len(xs) - 1)
+    lo, hi = int(rank), min(int(rank) + 1, len(xs) - 1)
+    return xs[lo] + (xs[hi] - xs[lo]) * (rank - lo)
+
+
+@given(lists(lists(integers(min_value=-100, max_value=100), min_size=1), min_size=1),
+       floats(min_value=0.0, max_value=1.0))
+def test_gk_summary(xss, phi):
+    epsilon = 0.05
+    merged = fast_stat.GKSummary(epsilon)
+    for xs in xss:
+        summary = fast_stat.GKSummary(epsilon)
+        summary.update(iter(xs))
+        merged.merge(pickle.loads(pickle.dumps(summary)))
+    merged = fast_stat.GKSummary.from_bytes(merged.to_bytes())
+    xs = sorted(x for xs in xss for x in xs)
+    n = len(xs)
+    assert merged.count() == n
+    assert merged.min() == xs[0] and merged.max() == xs[-1]
+    # some rank of the result is within epsilon * n of phi * n
+    x = merged.query(phi)
+    lo, hi = sum(1 for y in xs if y < x) + 1, sum(1 for y in xs if y <= x)
+    rank = ceil(phi * n)
+    assert lo - epsilon * n <= rank <= hi + epsilon * n
+    assert (lo - 1) / n - epsilon <= merged.rank(x) <= hi / n + epsilon
+
+
+def test_gk_summary_items():
+    summary = fast_stat.GKSummary(0.01)
+    summary.update([2**60 + 1, 2**60, 0.5, 2**60 + 2])
+    summary = pickle.loads(pickle.dumps(summary))
+    assert summary.min() == 0.5 and summary.max() == 2**60 + 2
+    assert summary.query(0.5) == 2**60
+    strings = fast_stat.GKSummary(0.01)
+    strings.update(['b', 'c', 'a'])
+    assert strings.query(0.0) == 'a'
+    for bad in (lambda: strings.insert(1), lambda: summary.rank('a'),
+                lambda: summary.merge(strings), lambda: summary.insert(float('nan'))):
+        try:
+            bad()
+        except Exception:
+            pass
+        else:
+            assert False
+
+
+@given(lists(integers(), min_size=1), data())
+def test_sampling(xs, data):
+    k = data.draw(integers(min_value=0, max_value=len(xs)))
+    seed = data.draw(integers(min_value=0, max_value=2**64 - 1))
+    ys = fast_stat.sample(xs, k, seed=seed)
+    assert len(ys) == k
+    assert Counter(ys) - Counter(xs) == Counter()
+    assert ys == fast_stat.sample(xs, k, seed=seed)
+    assert fast_stat.sample(iter(xs), k, seed=seed) == ys
+    weights = [i % 3 for i in range(len(xs))]
+    positive = sum(1 for w in weights if w > 0)
+    zs = fast_stat.weighted_sample(list(range(len(xs))), weights, min(k, positive), seed=seed)
+    assert len(set(zs)) == min(k, positive) and all(weights[z] > 0 for z in zs)
+    reservoir = fast_stat.Reservoir(k + 1, seed=seed)
+    reservoir.update(iter(xs))
+    assert reservoir.count() == len(xs)
+    assert len(reservoir.sample()) == min(k + 1, len(xs))
+    assert Counter(reservoir.sample()) - Counter(xs) == Counter()
+
+
+# width=32 is to prevent floating point OverflowError
+@given(lists(floats(allow_nan=False, allow_infinity=False, width=32), min_size=2))
+def test_iterable_input(xs):
+    assert isclose(fast_stat.stdev(iter(xs)), statistics.stdev(xs)) is True
+    assert isclose(fast_stat.mean(x for x in xs), fast_stat.mean(xs), abs_tol=1e-9) is True
+    assert fast_stat.median(iter(xs)) == fast_stat.median(xs)
+    ys = [int(x) % 5 for x in xs]
+    try:
+        m = fast_stat.mode_int(iter(ys))
+    except Exception:
+        # fast_stat.mode_int raises if there is no unique mode
+        return
+    assert m == statistics.mode(ys)
+
+
+@given(lists(floats(allow_nan=False, allow_infinity=False, width=32), min_size=2),
+       lists(floats(allow_nan=True, allow_infinity=False, width=32)))
+def test_nan_policy(xs, ys):
+    nans = [float('nan')] * len(ys)
+    zs = xs + [y for y in ys if y == y] + nans
+    funcs = (fast_stat.mean, fast_stat.stdev, fast_stat.median, fast_stat.median_low)
+    for f in funcs:
+        assert f(zs, nan_policy='omit') == f([z for z in zs if z == z])
+        assert f(iter(zs), nan_policy='omit') == f([z for z in zs if z == z])
+        if nans:
+            assert isnan(f(zs))
+            try:
+                f(zs, nan_policy='raise')
+                assert False
+            except Exception as e:
+                assert 'NaN' in str(e)
+    # errors depending on the data don't escape when NaN is propagated
+    for f in (fast_stat.mode_float, fast_stat.mode, lambda zs: fast_stat.kth_elem(zs, 0)):
+        assert isnan(f([float('nan'), 1.0, 2.0]))
+    assert fast_stat.mode([1.0, float('nan'), 1.0], nan_policy='omit') == 1.0
+    counts = fast_stat.value_counts_float(zs, nan_policy='omit')
+    assert counts == fast_stat.value_counts_float([z for z in zs if z == z])
+    assert sum(n for _, n in fast_stat.value_counts_float(zs)) == len(zs)
+
+
+# width=32 is to prevent floating point OverflowError
+@given(data())
+def test_quantiles(data):
+    xs = data.draw(lists(floats(allow_nan=False, allow_infinity=False, width=32), min_size=2))
+    n = data.draw(integers(min_value=1, max_value=20))
+    for method in ('exclusive', 'inclusive'):
+        for a, b in zip(fast_stat.quantiles(xs, n, method), statistics.quantiles(xs, n=n, method=method)):
+            assert isclose(a, b, abs_tol=1e-9) is True
+
+
 # width=32 is to prevent floating point OverflowError
 @given(lists(floats(allow_nan=False, allow_infinity=False, width=32), min_size=2))
 def test_stdev(xs):
@@ -69,6 +476,52 @@ def test_pvariance(xs):
     assert isclose(fast_stat.pvariance(xs), statistics.pvariance(xs)) is True
 
 
+# width=32 is to prevent floating point OverflowError
+@given(lists(lists(floats(allow_nan=False, allow_infinity=False, width=32)), min_size=1).filter(
+    lambda xss: sum(map(len, xss)) >= 2))
+def test_running_stats(xss):
+    stats = fast_stat.RunningStats()
+    for xs in xss:
+        stats.extend(xs)
+    xs = [x for xs in xss for x in xs]
+    assert stats.count() == len(xs)
+    assert stats.min() == min(xs) and stats.max() == max(xs)
+    assert isclose(stats.mean(), statistics.mean(xs), abs_tol=1e-9) is True
+    assert isclose(stats.variance(), statistics.variance(xs)) is True
+    assert isclose(stats.pstdev(), statistics.pstdev(xs)) is True
+
+
+# width=32 is to prevent floating point OverflowError
+@given(lists(lists(floats(allow_nan=False, allow_infinity=False, width=32)), min_size=1).filter(
+    lambda xss: sum(map(len, xss)) >= 2))
+def test_running_stats_merge(xss):
+    partial = []
+    for xs in xss:
+        stats = fast_stat.RunningStats()
+        stats.extend(xs)
+        partial.append(pickle.loads(pickle.dumps(stats)))
+    merged = partial[0]
+    for stats in partial[1:]:
+        merged = merged + stats
+    xs = [x for xs in xss for x in xs]
+    assert merged.count() == len(xs)
+    assert isclose(merged.mean(), statistics.mean(xs), abs_tol=1e-9) is True
+    assert isclose(merged.variance(), statistics.variance(xs), rel_tol=1e-6, abs_tol=1e-9) is True
+
+    class Other:
+        def __radd__(self, other):
+            return 'reflected'
+
+    # foreign operands get a chance to handle the addition
+    assert merged + Other() == 'reflected'
+    try:
+        merged + 1
+    except TypeError:
+        pass
+    else:
+        assert False
+
+
 # width=32 is to prevent floating point OverflowError
 @given(lists(floats(allow_nan=False, allow_infinity=False, min_value=0.0, width=32), min_size=1))
 def test_harmonic_mean(xs):
@@ -77,6 +530,18 @@ def test_harmonic_mean(xs):
 
 if __name__ == '__main__':
     test_with_msg('Testing kth_stat', test_kth_stat)
+    test_with_msg('Testing kth_elems', test_kth_elems)
+    test_with_msg('Testing kth_elem', test_kth_elem)
+    test_with_msg('Testing kth_elem of mixed data with NaN', test_kth_elem_mixed_nan)
+    test_with_msg('Testing kth seed', test_kth_seed)
+    test_with_msg('Testing radix select', test_radix_select)
+    test_with_msg('Testing mode', test_mode)
+
+    if 'multimode' in statistics.__dict__:
+        # multimode was introduced in python 3.8
+        test_with_msg('Testing multimode', test_multimode)
+
+    test_with_msg('Testing value_counts', test_value_counts)
     test_with_msg('Testing median', test_median)
     test_with_msg('Testing median_low', test_median_low)
     test_with_msg('Testing median_high', test_median_high)
@@ -85,6 +550,25 @@ if __name__ == '__main__':
     test_with_msg('Testing pstdev', test_pstdev)
     test_with_msg('Testing variance', test_variance)
     test_with_msg('Testing pvariance', test_pvariance)
+    test_with_msg('Testing buffer input', test_buffer_input)
+    test_with_msg('Testing iterable input', test_iterable_input)
+    test_with_msg('Testing nan_policy', test_nan_policy)
+    test_with_msg('Testing RunningStats', test_running_stats)
+    test_with_msg('Testing RunningStats merge', test_running_stats_merge)
+    test_with_msg('Testing HeavyHitters', test_heavy_hitters)
+    test_with_msg('Testing CountMinSketch', test_count_min_sketch)
+    test_with_msg('Testing count_distinct', test_count_distinct)
+    test_with_msg('Testing HyperLogLog', test_hyperloglog)
+    test_with_msg('Testing TDigest', test_tdigest)
+    test_with_msg('Testing KllSketch', test_kll_sketch)
+    test_with_msg('Testing P2Quantile', test_p2_quantiles)
+    test_with_msg('Testing GKSummary', test_gk_summary)
+    test_with_msg('Testing GKSummary items', test_gk_summary_items)
+    test_with_msg('Testing sampling', test_sampling)
+
+    if 'quantiles' in statistics.__dict__:
+        # quantiles were introduced in python 3.8
+        test_with_msg('Testing quantiles', test_quantiles)
 
     if 'harmonic_mean' in statistics.__dict__:
         # python's 3.5 statistics module doesn't have harmonic_mean